@@ -0,0 +1,286 @@
+//! Plays levels headless and prints how they went, for checking level difficulty
+//!
+//! ```text
+//! simulate --level levels/fifty_fifty.level.ron
+//! simulate --seed 1234 --script last.replay.ron
+//! simulate --seeds 0..500 > seeds.csv
+//! simulate --seeds 0..500 --bot > bot_scores.csv
+//! ```
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    native::main();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use combine_game::game::control::ControlInput;
+    use combine_game::game::goals::Countdown;
+    use combine_game::game::replay::{Replay, ReplayLevel};
+    use combine_game::headless::{HeadlessGame, HeadlessLevel};
+    use std::ops::Range;
+
+    const USAGE: &str = "Usage: simulate (--level <path> | --seed <n> | --seeds <from>..<to>) \
+    [--script <replay file> | --bot] [--max-secs <n>]
+
+  --level     Level file, relative to the assets folder
+  --seed      Random level seed
+  --seeds     Run every seed in the range and print one CSV line each
+  --script    Replay file whose inputs are played, and whose level is used if none is given.
+              Without one nothing is touched
+  --bot       Let the bot play instead
+  --max-secs  Give up on a level after this many seconds of simulation (default 180)";
+
+    const TICKS_PER_SEC: u64 = 60;
+    const DEFAULT_MAX_SECS: u64 = 180;
+
+    enum LevelChoice {
+        File(String),
+        Seed(u64),
+        Seeds(Range<u64>),
+    }
+
+    struct Args {
+        level: Option<LevelChoice>,
+        script: Option<Replay>,
+        bot: bool,
+        max_secs: u64,
+    }
+
+    impl Args {
+        fn parse() -> Result<Self, String> {
+            let mut args = Self {
+                level: None,
+                script: None,
+                bot: false,
+                max_secs: DEFAULT_MAX_SECS,
+            };
+            let mut iter = std::env::args().skip(1);
+            while let Some(arg) = iter.next() {
+                let mut value = || iter.next().ok_or(format!("{} needs a value", arg));
+                match arg.as_str() {
+                    "--level" => args.level = Some(LevelChoice::File(value()?)),
+                    "--seed" => args.level = Some(LevelChoice::Seed(parse_number(&value()?)?)),
+                    "--seeds" => args.level = Some(LevelChoice::Seeds(parse_range(&value()?)?)),
+                    "--script" => {
+                        let path = value()?;
+                        let contents = std::fs::read_to_string(&path)
+                            .map_err(|e| format!("Can't read {}: {}", path, e))?;
+                        args.script = Some(Replay::from_ron(&contents)?);
+                    }
+                    "--bot" => args.bot = true,
+                    "--max-secs" => args.max_secs = parse_number(&value()?)?,
+                    _ => return Err(format!("Unknown argument {}", arg)),
+                }
+            }
+            if args.bot && args.script.is_some() {
+                return Err("--script and --bot can't be used together".into());
+            }
+            if args.level.is_none() {
+                args.level = args.script.as_ref().map(|script| match &script.level {
+                    ReplayLevel::Seed(seed) => LevelChoice::Seed(*seed),
+                    ReplayLevel::File(path) => LevelChoice::File(path.clone()),
+                });
+            }
+            Ok(args)
+        }
+    }
+
+    fn parse_number(value: &str) -> Result<u64, String> {
+        value
+            .parse()
+            .map_err(|_| format!("{} isn't a whole number", value))
+    }
+
+    fn parse_range(value: &str) -> Result<Range<u64>, String> {
+        let (from, to) = value
+            .split_once("..")
+            .ok_or(format!("{} isn't a range like 0..100", value))?;
+        Ok(parse_number(from)?..parse_number(to)?)
+    }
+
+    /// Where inputs come from, once per tick
+    enum Policy<'a> {
+        Idle,
+        Script {
+            inputs: &'a [(u64, ControlInput)],
+            next: usize,
+        },
+        /// The game's own bot sets the inputs
+        Bot,
+    }
+
+    impl<'a> Policy<'a> {
+        fn new(script: Option<&'a Replay>, bot: bool) -> Self {
+            match script {
+                Some(script) => Self::Script {
+                    inputs: script.inputs(),
+                    next: 0,
+                },
+                None if bot => Self::Bot,
+                None => Self::Idle,
+            }
+        }
+
+        /// New input for this tick, if it's changed
+        fn input(&mut self, tick: u64) -> Option<ControlInput> {
+            match self {
+                Self::Idle | Self::Bot => None,
+                Self::Script { inputs, next } => {
+                    let mut input = None;
+                    while let Some((input_tick, next_input)) = inputs.get(*next) {
+                        if *input_tick > tick {
+                            break;
+                        }
+                        input = Some(*next_input);
+                        *next += 1;
+                    }
+                    input
+                }
+            }
+        }
+    }
+
+    struct Report {
+        criteria: Vec<String>,
+        min_weight: f32,
+        countdown_secs: f32,
+        mix: String,
+        /// (level clock secs, weight on the balance), once a second
+        timeline: Vec<(f32, f32)>,
+        countdown_started: Option<f32>,
+        /// None if the level didn't finish in time
+        results: Option<(Vec<String>, f32)>,
+    }
+
+    fn simulate(
+        level: HeadlessLevel,
+        policy: &mut Policy,
+        max_secs: u64,
+    ) -> Result<Report, String> {
+        // A script dismisses the goals itself, when the player did
+        let mut game = match policy {
+            Policy::Script { .. } => HeadlessGame::with_goals_shown(level)?,
+            Policy::Idle | Policy::Bot => HeadlessGame::new(level)?,
+        };
+        if let Policy::Bot = policy {
+            game.enable_bot();
+        }
+        let criteria = game.criteria();
+        let mut report = Report {
+            criteria: criteria.to_strings(),
+            min_weight: criteria.min_weight,
+            countdown_secs: criteria.countdown_time_secs,
+            mix: format!("{} {}", criteria.target_mix, criteria.mix_measure),
+            timeline: Vec::new(),
+            countdown_started: None,
+            results: None,
+        };
+
+        let mut tick = 0;
+        while tick < max_secs * TICKS_PER_SEC {
+            if let Some(input) = policy.input(tick) {
+                game.set_input(input);
+            }
+            game.step(1);
+            tick += 1;
+
+            if report.countdown_started.is_none() {
+                if let Countdown::Active { .. } = game.countdown() {
+                    report.countdown_started = Some(game.elapsed_secs());
+                }
+            }
+            if tick % TICKS_PER_SEC == 0 {
+                report
+                    .timeline
+                    .push((game.elapsed_secs(), game.balance().total_weight()));
+            }
+            if game.finished() {
+                report.results = Some(game.results());
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    fn print_report(report: &Report) {
+        for line in report.criteria.iter() {
+            println!("{}", line);
+        }
+        println!("Countdown: {:.0}s", report.countdown_secs);
+        println!();
+        println!("Weight timeline:");
+        for (secs, weight) in report.timeline.iter() {
+            println!("  {:>6.1}s  {:.2}", secs, weight);
+        }
+        println!();
+        match report.countdown_started {
+            Some(secs) => println!("Countdown started at {:.1}s", secs),
+            None => println!("Countdown never started"),
+        }
+        match &report.results {
+            Some((ratios, score)) => {
+                println!("Results:");
+                for line in ratios.iter() {
+                    println!("  {}", line);
+                }
+                println!("Score: {:.2}", score);
+            }
+            None => println!("Didn't finish in time"),
+        }
+    }
+
+    fn optional(value: Option<f32>) -> String {
+        value.map(|v| format!("{:.2}", v)).unwrap_or_default()
+    }
+
+    fn run_one(level: HeadlessLevel, args: &Args) {
+        let mut policy = Policy::new(args.script.as_ref(), args.bot);
+        match simulate(level, &mut policy, args.max_secs) {
+            Ok(report) => print_report(&report),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    pub fn main() {
+        let args = match Args::parse() {
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("{}\n\n{}", e, USAGE);
+                std::process::exit(2);
+            }
+        };
+        match &args.level {
+            Some(LevelChoice::Seeds(seeds)) => {
+                println!("seed,min_weight,countdown_secs,mix,countdown_started_secs,score");
+                for seed in seeds.clone() {
+                    let mut policy = Policy::new(args.script.as_ref(), args.bot);
+                    match simulate(HeadlessLevel::Seed(seed), &mut policy, args.max_secs) {
+                        Ok(report) => println!(
+                            "{},{:.2},{:.0},\"{}\",{},{}",
+                            seed,
+                            report.min_weight,
+                            report.countdown_secs,
+                            report.mix,
+                            optional(report.countdown_started),
+                            optional(report.results.map(|(_, score)| score)),
+                        ),
+                        Err(e) => eprintln!("Seed {}: {}", seed, e),
+                    }
+                }
+            }
+            Some(LevelChoice::File(path)) => run_one(HeadlessLevel::File(path.clone()), &args),
+            Some(LevelChoice::Seed(seed)) => run_one(HeadlessLevel::Seed(*seed), &args),
+            None => {
+                eprintln!("No level given\n\n{}", USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use crate::manifest::AssetGroup;
 use crate::SfxAudio;
+use bevy::asset::HandleId;
 use bevy::log::info;
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioControl, AudioSource};
@@ -18,14 +20,20 @@ pub enum AudioAsset {
 #[derive(Deref)]
 pub struct AudioAssetStore(EnumMap<AudioAsset, Handle<AudioSource>>);
 
-impl AudioAssetStore {
-    fn new(asset_server: &AssetServer) -> Self {
+impl AssetGroup for AudioAssetStore {
+    fn load(asset_server: &AssetServer) -> Self {
         Self(enum_map! {
             AudioAsset::ShortBeep => asset_server.load(AudioAsset::ShortBeep.to_filename()),
             AudioAsset::Acquired => asset_server.load(AudioAsset::Acquired.to_filename())
         })
     }
 
+    fn handle_ids(&self) -> Vec<HandleId> {
+        self.values().map(|handle| handle.id).collect()
+    }
+}
+
+impl AudioAssetStore {
     fn get(&self, asset: AudioAsset) -> Handle<AudioSource> {
         self[asset].clone()
     }
@@ -40,10 +48,6 @@ impl AudioAsset {
     }
 }
 
-pub fn setup_audio(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(AudioAssetStore::new(&asset_server));
-}
-
 pub fn triggered_audio_system(
     mut event_reader: EventReader<AudioTriggerEvent>,
     audio_asset_store: Res<AudioAssetStore>,
@@ -1,40 +1,52 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::game::ball::{Ball, BallKind};
-use crate::game::goals::Mix;
+use crate::game::ball::BallKind;
+use crate::game::goals::{Countdown, Mix, MixMeasure};
+use crate::game::level::TableLayout;
+use crate::game::table::Table;
+use crate::game::versus::{self, Versus};
+use crate::game::weighing::{WeighingCandidates, WeighingZone};
 use crate::game::GameOnlyMarker;
-use std::collections::HashMap;
+use crate::manifest::{Fonts, Sprites};
+// Ordered so totals are always summed in the same order
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone)]
+/// What's been weighed on a table's balance, kept on the balance
+#[derive(Component, Debug, Clone)]
 pub struct BalanceCounter {
-    ball_count: HashMap<BallKind, usize>,
+    ball_count: BTreeMap<BallKind, usize>,
+    ball_weight: BTreeMap<BallKind, f32>,
 }
 
 impl BalanceCounter {
     pub fn new() -> Self {
         BalanceCounter {
-            ball_count: HashMap::new(),
+            ball_count: BTreeMap::new(),
+            ball_weight: BTreeMap::new(),
         }
     }
 
-    pub fn reset(&mut self) {
-        self.ball_count.clear();
-    }
-
-    fn incr(&mut self, ball_id: BallKind) {
+    pub fn incr(&mut self, ball_id: BallKind) {
+        *self.ball_weight.entry(ball_id.clone()).or_insert(0.0) += ball_id.mass();
         *self.ball_count.entry(ball_id).or_insert(0) += 1;
     }
 
-    fn decr(&mut self, ball_id: BallKind) {
+    pub fn decr(&mut self, ball_id: BallKind) {
         match self.ball_count.get_mut(&ball_id) {
             Some(0) => warn!("Tried to decr 0 in map (key: {:?}), not doing.", ball_id),
-            Some(val) => *val -= 1,
+            Some(val) => {
+                *val -= 1;
+                if let Some(weight) = self.ball_weight.get_mut(&ball_id) {
+                    // Clamp so float error can't leave a tiny negative weight behind
+                    *weight = (*weight - ball_id.mass()).max(0.0);
+                }
+            }
             None => warn!("Tried to decr nonexistent count (key: {:?})", ball_id),
         }
     }
 
-    fn total_count(&self) -> usize {
+    pub fn total_count(&self) -> usize {
         let mut i = 0;
         for v in self.ball_count.values() {
             i += v;
@@ -42,49 +54,103 @@ impl BalanceCounter {
         i
     }
 
+    pub fn count(&self, ball_id: &BallKind) -> usize {
+        self.ball_count.get(ball_id).copied().unwrap_or(0)
+    }
+
     pub fn total_weight(&self) -> f32 {
-        //TODO, support dynamic weight of balls
-        0.8 * (self.total_count() as f32)
+        self.ball_weight.values().sum()
     }
 
-    pub fn calculate_ratio(&self) -> f32 {
-        // TODO: Do this properly
-        let a = *self.ball_count.get(&BallKind::Blue).unwrap_or(&0);
-        let b = *self.ball_count.get(&BallKind::Red).unwrap_or(&0);
-        a as f32 / b as f32
+    fn amount(&self, ball_id: &BallKind, measure: MixMeasure) -> f32 {
+        match measure {
+            MixMeasure::Count => *self.ball_count.get(ball_id).unwrap_or(&0) as f32,
+            MixMeasure::Weight => *self.ball_weight.get(ball_id).unwrap_or(&0.0),
+        }
     }
 
-    pub fn ratios_and_score(&self, target_mix: &Mix) -> (String, String, f32) {
-        let (a_type, a_target, b_type, b_target) = match target_mix {
-            Mix::FiftyFifty => (BallKind::Blue, 50f32, BallKind::Red, 50f32),
-            Mix::AB {
-                a_pct,
-                a_kind,
-                b_kind,
-            } => (
-                a_kind.clone(),
-                *a_pct as f32,
-                b_kind.clone(),
-                (100 - *a_pct) as f32,
-            ),
-        };
+    fn total_amount(&self, measure: MixMeasure) -> f32 {
+        match measure {
+            MixMeasure::Count => self.total_count() as f32,
+            MixMeasure::Weight => self.total_weight(),
+        }
+    }
 
-        let total = self.total_count() as f32;
+    /// Fraction of what's on the balance that's this ingredient, 0 when it's empty
+    pub fn share(&self, ball_id: &BallKind, measure: MixMeasure) -> f32 {
+        let total = self.total_amount(measure);
+        if total > 0.0 {
+            self.amount(ball_id, measure) / total
+        } else {
+            0.0
+        }
+    }
 
-        let a_true_pct = {
-            let v = *self.ball_count.get(&a_type).unwrap_or(&0usize);
-            (v as f32 / total) * 100.0
-        };
-        let b_true_pct = {
-            let v = *self.ball_count.get(&b_type).unwrap_or(&0usize);
-            (v as f32 / total) * 100.0
-        };
+    /// How each ingredient in the mix turned out
+    pub fn part_results(&self, target_mix: &Mix, measure: MixMeasure) -> Vec<PartResult> {
+        target_mix
+            .parts()
+            .iter()
+            .map(|part| {
+                let target = part.pct as f32;
+                let actual = self.share(&part.kind, measure) * 100.0;
+                PartResult {
+                    kind: part.kind.clone(),
+                    target,
+                    actual,
+                    score: pct_to_score(target, actual),
+                }
+            })
+            .collect()
+    }
 
-        let a_result_str = format!("{}. Goal {:.2}, Actual {:.2}", a_type, a_target, a_true_pct);
-        let b_result_str = format!("{}. Goal {:.2}, Actual {:.2}", b_type, b_target, b_true_pct);
+    /// A result line for each ingredient in the mix and the overall score out of 100
+    pub fn ratios_and_score(&self, target_mix: &Mix, measure: MixMeasure) -> (Vec<String>, f32) {
+        let parts = self.part_results(target_mix, measure);
+        let results = parts
+            .iter()
+            .map(|part| {
+                format!(
+                    "{}. Goal {:.2}, Actual {:.2}",
+                    part.kind, part.target, part.actual
+                )
+            })
+            .collect();
+        (results, PartResult::combined_score(&parts))
+    }
+}
 
-        let score = pct_to_score(a_target, a_true_pct) + pct_to_score(b_target, b_true_pct);
-        (a_result_str, b_result_str, score)
+/// One ingredient's share of the mix against its target, as percentages
+#[derive(Debug, Clone)]
+pub struct PartResult {
+    pub kind: BallKind,
+    pub target: f32,
+    pub actual: f32,
+    /// Out of 50
+    pub score: f32,
+}
+
+impl PartResult {
+    /// Each ingredient scores up to 50, scaled so getting them all right is worth 100 however
+    /// many there are
+    pub fn combined_score(parts: &[PartResult]) -> f32 {
+        if parts.is_empty() {
+            return 0.0;
+        }
+        parts.iter().map(|part| part.score).sum::<f32>() * 2.0 / (parts.len() as f32)
+    }
+
+    /// Which way it missed the target, if it did
+    pub fn miss_text(&self) -> String {
+        let difference = self.actual - self.target;
+        // Within a percent still gets full marks, see pct_to_score
+        if difference.abs() < 1.0 {
+            "on target".to_string()
+        } else if difference > 0.0 {
+            format!("over by {:.2}", difference)
+        } else {
+            format!("under by {:.2}", -difference)
+        }
     }
 }
 
@@ -116,82 +182,59 @@ fn test_pct_to_score() {
     assert_eq!(0.0, to_2dp(pct_to_score(target, 20.0)));
 }
 
-fn other_entity_if_match(match_entity: &Entity, e1: Entity, e2: Entity) -> Option<Entity> {
-    if e1 == *match_entity {
-        Some(e2)
-    } else if e2 == *match_entity {
-        Some(e1)
-    } else {
-        None
+#[test]
+fn test_three_way_score_is_normalised() {
+    use crate::game::ingredients::test_kind;
+
+    let (a, b, c) = (test_kind("A"), test_kind("B"), test_kind("C"));
+    let mut counter = BalanceCounter::new();
+    for _ in 0..5 {
+        counter.incr(a.clone());
+    }
+    for _ in 0..3 {
+        counter.incr(b.clone());
+    }
+    for _ in 0..2 {
+        counter.incr(c.clone());
     }
-}
 
-pub fn ball_sensor_system(
-    mut active_events: EventReader<CollisionEvent>,
-    balance_sensor_query: Query<Entity, With<BalanceSensor>>,
-    ball_query: Query<&Ball>,
-    mut balance_text_query: Query<&mut Text, With<BalanceText>>,
-    mut balance_counter: ResMut<BalanceCounter>,
-) {
-    //TODO: Consider rework below:
-    //
-    // Right now this system works by counting balls that enter or leave the sensor zone
-    // This is flawed because:
-    // 1. It counts too soon, a ball's weight is counted before it even hits the floor
-    // 2. Balls can pile up outside of the sense
-    //
-    // Fixing (2) will only make (1) worse/more apparent
-    //
-    // Poss Solution:
-    // Keep track of entities in bigger sensor area, don't count them until their velocity is ~0
-    // Probably revisit those with ~0 vel in case they are nudged
+    let mix = Mix::new(vec![(a, 50), (b, 30), (c, 20)]);
+    let (results, score) = counter.ratios_and_score(&mix, MixMeasure::Count);
+    assert_eq!(3, results.len());
+    assert_eq!(100.0, score);
 
-    if !active_events.is_empty() {
-        let balance_sensor_entity = balance_sensor_query.single();
-        let mut counter_changed = false;
-        for event in active_events.iter() {
-            match event {
-                CollisionEvent::Started(e1, e2, _flags) => {
-                    if let Some(other_entity) =
-                        other_entity_if_match(&balance_sensor_entity, *e1, *e2)
-                    {
-                        if let Ok(ball) = ball_query.get(other_entity) {
-                            balance_counter.incr(ball.0.clone());
-                            counter_changed = true;
-                        }
-                    }
-                }
-                CollisionEvent::Stopped(e1, e2, _flags) => {
-                    if let Some(other_entity) =
-                        other_entity_if_match(&balance_sensor_entity, *e1, *e2)
-                    {
-                        if let Ok(ball) = ball_query.get(other_entity) {
-                            balance_counter.decr(ball.0.clone());
-                            counter_changed = true;
-                        }
-                    }
-                }
-            }
-        }
-        if counter_changed {
-            println!("Counter changed: {:?}", balance_counter);
-            let total_weight = balance_counter.total_weight();
-            if let Ok(mut text) = balance_text_query.get_single_mut() {
-                text.sections[0].value = format!("{:.2}", total_weight);
-            }
-        }
-    }
+    // Nothing weighed is 0% of everything rather than NaN
+    let empty = BalanceCounter::new().part_results(&mix, MixMeasure::Count);
+    assert!(empty.iter().all(|part| part.actual == 0.0));
 }
 
-#[derive(Component)]
-pub struct BalanceSensor;
-
 #[derive(Component)]
 pub struct BalanceText;
 
-pub fn spawn_balance(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("Quicksand-Regular.ttf");
-    let (collider_shape, sensor_shape) = {
+/// A balance for each table, each keeping its own count and countdown
+pub fn spawn_balance(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    sprites: Res<Sprites>,
+    layout: Res<TableLayout>,
+    versus: Option<Res<Versus>>,
+) {
+    let offsets = Table::offsets(versus::table_count(versus.as_deref()));
+    for (table, offset) in offsets.into_iter().enumerate() {
+        let position = layout.balance_position + Vec2::new(offset, 0.0);
+        spawn_table_balance(&mut commands, &fonts, &sprites, position, Table(table));
+    }
+}
+
+fn spawn_table_balance(
+    commands: &mut Commands,
+    fonts: &Fonts,
+    sprites: &Sprites,
+    position: Vec2,
+    table: Table,
+) {
+    let font = fonts.regular.clone();
+    let (collider_shape, zone_shape, zone_transform) = {
         let y_offset = -6.0;
         let thickness = 4.0;
         let height = 24.0;
@@ -208,13 +251,16 @@ pub fn spawn_balance(mut commands: Commands, asset_server: Res<AssetServer>) {
             (Vec2::new(0.0, (thickness / 2.0) + y_offset), 0.0, bottom),
             (Vec2::new(offset, (height / 2.0) + y_offset), 0.0, side),
         ];
-        let sensor_shape = Collider::cuboid(width / 2.0, height / 2.0);
-        (collider_shape, sensor_shape)
+        // The weighing zone is wider and taller than the pan so balls piled up above the rim
+        // still get weighed once they settle
+        let zone_shape = Collider::cuboid((width / 2.0) + 4.0, height);
+        let zone_transform = Transform::from_xyz(0.0, height + thickness + y_offset, 0.0);
+        (collider_shape, zone_shape, zone_transform)
     };
 
-    let transform = Transform::from_xyz(0.0, -70.0, 0.0);
-    let text_transform = Transform::from_xyz(-8.0, -96.0, 1.0);
-    let texture = asset_server.load("balance.png");
+    let transform = Transform::from_xyz(position.x, position.y, 0.0);
+    let text_transform = Transform::from_xyz(position.x - 8.0, position.y - 26.0, 1.0);
+    let texture = sprites.balance.clone();
     commands
         .spawn()
         .insert_bundle(SpriteBundle {
@@ -226,14 +272,17 @@ pub fn spawn_balance(mut commands: Commands, asset_server: Res<AssetServer>) {
         .insert(Collider::compound(collider_shape))
         .insert(CollisionGroups::new(0b0001, 0b0111))
         .insert(GameOnlyMarker)
+        .insert(table)
+        .insert(BalanceCounter::new())
+        .insert(Countdown::Inactive)
+        .insert(WeighingCandidates::default())
         .with_children(|parent| {
             parent
                 .spawn()
-                .insert(sensor_shape)
+                .insert(zone_shape)
                 .insert(Sensor)
-                .insert(ActiveEvents::COLLISION_EVENTS)
-                .insert(BalanceSensor)
-                .insert(Transform::from_xyz(0.0, 10.0, 0.0));
+                .insert(WeighingZone)
+                .insert(zone_transform);
         });
     commands
         .spawn()
@@ -256,5 +305,6 @@ pub fn spawn_balance(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         })
         .insert(GameOnlyMarker)
+        .insert(table)
         .insert(BalanceText);
 }
@@ -1,36 +1,46 @@
 use crate::game::cup::Cup;
+use crate::game::ingredients::BallKindDef;
 use crate::game::not_a_cup::Jar;
+use crate::game::table::Table;
 use crate::game::GameOnlyMarker;
+use crate::manifest::BallSprites;
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
-use bevy_rapier2d::dynamics::{GravityScale, RigidBody, Sleeping};
-use bevy_rapier2d::geometry::{Collider, CollisionGroups};
+use bevy_rapier2d::dynamics::{GravityScale, RigidBody, Sleeping, Velocity};
+use bevy_rapier2d::geometry::{Collider, ColliderMassProperties, CollisionGroups};
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 pub fn spawn_ball_system(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    cup_query: Query<(&Transform, &Cup)>,
+    ball_sprites: Res<BallSprites>,
+    cup_query: Query<(&Transform, &Cup, &Table)>,
     jar_query: Query<(&Transform, &Jar)>,
     mut event_reader: EventReader<SpawnBallEvent>,
+    mut balls_spawned: ResMut<BallsSpawned>,
 ) {
     for _ in event_reader.iter() {
-        for (transform, cup) in cup_query.iter() {
+        for (transform, cup, table) in cup_query.iter() {
+            balls_spawned.add(*table);
             spawn_ball(
                 transform.translation.x,
-                cup.0.clone(),
+                cup.kind.clone(),
                 &mut commands,
-                &asset_server,
+                &ball_sprites,
             );
         }
 
         let jar_offset = -5.0;
         for (transform, jar) in jar_query.iter() {
+            // Only the first table ever has a jar, versus turns them into cups
+            balls_spawned.add(Table(0));
             spawn_ball(
                 transform.translation.x + jar_offset,
                 jar.0.clone(),
                 &mut commands,
-                &asset_server,
+                &ball_sprites,
             );
         }
     }
@@ -39,6 +49,24 @@ pub fn spawn_ball_system(
 #[derive(Clone, Debug)]
 pub struct SpawnBallEvent;
 
+/// Balls dropped into each table's containers this level. Counted as they're spawned, as balls
+/// can be despawned before the level's over
+#[derive(Default)]
+pub struct BallsSpawned(Vec<usize>);
+
+impl BallsSpawned {
+    fn add(&mut self, table: Table) {
+        if self.0.len() <= table.0 {
+            self.0.resize(table.0 + 1, 0);
+        }
+        self.0[table.0] += 1;
+    }
+
+    pub fn get(&self, table: Table) -> usize {
+        self.0.get(table.0).copied().unwrap_or(0)
+    }
+}
+
 pub fn debug_spawn_ball_input_system(
     input: Res<Input<KeyCode>>,
     mut event_writer: EventWriter<SpawnBallEvent>,
@@ -48,27 +76,70 @@ pub fn debug_spawn_ball_input_system(
     }
 }
 
-#[derive(Component, Hash, PartialEq, Eq, Debug, Clone)]
-pub enum BallKind {
-    Red,
-    Blue,
-}
+/// A kind of ball, as loaded from the [BallKindRegistry](crate::game::ingredients::BallKindRegistry).
+/// Kinds are identified by name, cloning one is cheap.
+#[derive(Component, Debug, Clone)]
+pub struct BallKind(Arc<BallKindDef>);
 
 impl BallKind {
+    pub fn from_def(def: &BallKindDef) -> Self {
+        Self(Arc::new(def.clone()))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
     pub fn to_color(&self) -> Color {
-        match self {
-            Self::Red => Color::RED,
-            Self::Blue => Color::BLUE,
-        }
+        let (r, g, b) = self.0.colour;
+        Color::rgb(r, g, b)
+    }
+
+    pub fn mass(&self) -> f32 {
+        self.0.mass
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.0.radius
+    }
+
+    pub fn sprite(&self) -> &str {
+        &self.0.sprite
+    }
+
+    /// Density to give rapier so the simulated mass of a ball is proportional to [Self::mass]
+    fn density(&self) -> f32 {
+        self.mass() / (std::f32::consts::PI * self.radius() * self.radius())
+    }
+}
+
+impl PartialEq for BallKind {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+impl Eq for BallKind {}
+
+impl Hash for BallKind {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name().hash(state)
+    }
+}
+
+impl PartialOrd for BallKind {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for BallKind {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.name().cmp(other.name())
     }
 }
 
 impl Display for BallKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Red => write!(f, "Red"),
-            Self::Blue => write!(f, "Blue"),
-        }
+        write!(f, "{}", self.name())
     }
 }
 
@@ -79,17 +150,20 @@ fn spawn_ball(
     x_offset: f32,
     ball_kind: BallKind,
     commands: &mut Commands,
-    asset_server: &AssetServer,
+    ball_sprites: &BallSprites,
 ) {
-    let radius = 2.8;
-    let sprite_tex = asset_server.load("ball.png");
+    let radius = ball_kind.radius();
+    let sprite_tex = ball_sprites.get(ball_kind.sprite());
     let transform = Transform::from_xyz(x_offset, 100.0, 1.0);
     let color = ball_kind.to_color();
+    let density = ball_kind.density();
     commands
         .spawn()
         .insert(RigidBody::Dynamic)
         .insert(Collider::ball(radius))
+        .insert(ColliderMassProperties::Density(density))
         .insert(Sleeping::disabled())
+        .insert(Velocity::default())
         //TODO: Ball CCD probably good but also likely a performance bottleneck. Revisit
         // .insert(Ccd::enabled())
         .insert(GravityScale(1.0))
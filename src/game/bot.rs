@@ -0,0 +1,181 @@
+use crate::game::balance::BalanceCounter;
+use crate::game::control::{ControlInput, DoorMove, Tilt};
+use crate::game::cup::Cup;
+use crate::game::goals::{Countdown, LevelCriteria};
+use crate::game::level::{ContainerKind, TableLayout};
+use crate::game::table::Table;
+use crate::game::{timestep, BallKind, TickLabel};
+use crate::GameState;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+
+// How far a cup is tipped to pour, in radians either side of upright
+const POUR_ANGLE: f32 = 2.0;
+// Close enough to the angle it's aiming for to stop turning
+const ANGLE_TOLERANCE: f32 = 0.1;
+// Once there's enough on the balance, a share this close to the target is left alone
+const MIX_TOLERANCE: f32 = 0.02;
+
+/// While this resource exists the bot plays this table, through [ControlInput] like a player.
+/// On the first table it has all of [ControlInput], on any other it's a versus opponent and only
+/// touches its own table's containers
+pub struct Bot(pub Table);
+
+/// Set while the bot's playing the menu's demo, see [crate::menu]. Demo levels aren't the
+/// player's, so they aren't scored or recorded
+pub struct Demo;
+
+/// The ingredient furthest below its share of the target mix. None once there's enough on the
+/// balance and every share is close enough
+pub fn ingredient_to_pour(balance: &BalanceCounter, criteria: &LevelCriteria) -> Option<BallKind> {
+    let (kind, shortfall) = criteria
+        .target_mix
+        .parts()
+        .iter()
+        .map(|part| {
+            let target = part.pct as f32 / 100.0;
+            let shortfall = target - balance.share(&part.kind, criteria.mix_measure);
+            (&part.kind, shortfall)
+        })
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))?;
+
+    if balance.total_weight() > criteria.min_weight && shortfall < MIX_TOLERANCE {
+        None
+    } else {
+        Some(kind.clone())
+    }
+}
+
+fn tilt_towards(angle: f32, target: f32) -> Tilt {
+    let diff = target - angle;
+    if diff > ANGLE_TOLERANCE {
+        Tilt::Anticlockwise
+    } else if diff < -ANGLE_TOLERANCE {
+        Tilt::Clockwise
+    } else {
+        Tilt::Still
+    }
+}
+
+/// Tips the cups holding the ingredient that's most needed towards the balance and stands the
+/// rest back up. The jar door is opened when the jar holds it
+fn bot_system(
+    bot: Option<Res<Bot>>,
+    mut control: ResMut<ControlInput>,
+    criteria: Res<LevelCriteria>,
+    layout: Res<TableLayout>,
+    balances: Query<(&Table, &BalanceCounter, &Countdown, &Transform)>,
+    cups: Query<(&Table, &Transform, &Cup)>,
+) {
+    let bot_table = match bot {
+        Some(bot) => bot.0,
+        None => return,
+    };
+    let (balance, countdown, balance_x) = match balances
+        .iter()
+        .find(|(table, ..)| **table == bot_table)
+    {
+        Some((_, balance, countdown, transform)) => (balance, countdown, transform.translation.x),
+        None => return,
+    };
+
+    let pour = match *countdown {
+        Countdown::Active {
+            end_calculated: true,
+            ..
+        } => None,
+        _ => ingredient_to_pour(balance, &criteria),
+    };
+
+    // On the first table the bot has the controls to itself
+    if bot_table == Table(0) {
+        *control = ControlInput::default();
+    }
+    for (_, transform, cup) in cups.iter().filter(|(table, ..)| **table == bot_table) {
+        let target = match &pour {
+            Some(kind) if *kind == cup.kind => {
+                if transform.translation.x < balance_x {
+                    -POUR_ANGLE
+                } else {
+                    POUR_ANGLE
+                }
+            }
+            _ => 0.0,
+        };
+        let (angle, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+        if let Some(tilt) = control.tilts.get_mut(cup.slot) {
+            *tilt = tilt_towards(angle, target);
+        }
+    }
+
+    // The jar door and the goals are player one's
+    if bot_table != Table(0) {
+        return;
+    }
+
+    let jar_needed = pour.map_or(false, |kind| {
+        layout
+            .containers
+            .iter()
+            .any(|container| container.container == ContainerKind::Jar && container.kind == kind)
+    });
+    control.door = if jar_needed {
+        DoorMove::Up
+    } else {
+        DoorMove::Down
+    };
+    // Skip past the goals overlay, it only matters before the countdown
+    control.dismiss = matches!(countdown, Countdown::Inactive);
+}
+
+/// The bot's game is over once we're back on the menu
+fn stop_system(mut commands: Commands) {
+    commands.remove_resource::<Bot>();
+    commands.remove_resource::<Demo>();
+}
+
+pub struct BotPlugin;
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(stop_system));
+        // Before the replay takes its input, so a bot's level is recorded like any other
+        timestep::add_tick_systems(
+            app,
+            SystemSet::on_update(GameState::Game)
+                .with_system(bot_system.before(TickLabel::Controls)),
+        );
+    }
+}
+
+#[test]
+fn test_ingredient_to_pour() {
+    use crate::game::goals::{Mix, MixMeasure};
+    use crate::game::ingredients::test_kind;
+
+    let (a, b) = (test_kind("A"), test_kind("B"));
+    let criteria = LevelCriteria {
+        ingredients: vec![a.clone(), b.clone()],
+        min_weight: 5.0,
+        target_mix: Mix::new(vec![(a.clone(), 25), (b.clone(), 75)]),
+        mix_measure: MixMeasure::Count,
+        countdown_time_secs: 10.0,
+    };
+
+    let mut balance = BalanceCounter::new();
+    assert_eq!(Some(b.clone()), ingredient_to_pour(&balance, &criteria));
+
+    for _ in 0..3 {
+        balance.incr(b.clone());
+    }
+    assert_eq!(Some(a.clone()), ingredient_to_pour(&balance, &criteria));
+
+    // Right mix, but not enough of it yet
+    balance.incr(a.clone());
+    assert!(ingredient_to_pour(&balance, &criteria).is_some());
+
+    for _ in 0..3 {
+        balance.incr(b.clone());
+    }
+    balance.incr(a);
+    assert_eq!(None, ingredient_to_pour(&balance, &criteria));
+}
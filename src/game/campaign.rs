@@ -0,0 +1,229 @@
+use crate::game::goals::LevelCompleteEvent;
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level::{self, LevelDef};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+pub const CAMPAIGN_PATH: &str = "levels/main.campaign.ron";
+
+#[derive(Debug, Deserialize)]
+pub struct CampaignEntryDef {
+    /// Path to a level file, relative to the assets folder
+    pub level: String,
+    pub pass_score: f32,
+}
+
+/// The ordered list of levels making up the campaign, loaded from a `.campaign.ron` file
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "37c4f1ed-8c9d-4fa5-8ffb-0439dd9ea17f"]
+pub struct CampaignAsset {
+    pub levels: Vec<CampaignEntryDef>,
+}
+
+impl CampaignAsset {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        for (i, entry) in self.levels.iter().enumerate() {
+            if !(0.0..=100.0).contains(&entry.pass_score) {
+                anyhow::bail!(
+                    "Invalid campaign, levels[{}].pass_score: must be between 0 and 100",
+                    i
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct CampaignLoader;
+
+impl AssetLoader for CampaignLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let campaign: CampaignAsset = ron::de::from_bytes(bytes)?;
+            campaign.validate()?;
+            load_context.set_default_asset(LoadedAsset::new(campaign));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign.ron"]
+    }
+}
+
+pub struct CampaignLevel {
+    pub path: String,
+    pub name: String,
+    pub pass_score: f32,
+    pub level: Handle<LevelDef>,
+}
+
+#[derive(Default)]
+pub struct Campaign {
+    levels: Vec<CampaignLevel>,
+}
+
+impl Campaign {
+    /// Build the campaign from its loaded levels, levels that failed to load or are invalid are
+    /// logged and left out
+    pub fn from_loaded(
+        asset: Option<&CampaignAsset>,
+        level_handles: &[Handle<LevelDef>],
+        level_assets: &Assets<LevelDef>,
+        registry: &BallKindRegistry,
+    ) -> Self {
+        let entries = match asset {
+            Some(asset) => asset.levels.iter(),
+            None => {
+                error!("Failed to load campaign {}", CAMPAIGN_PATH);
+                return Self { levels: Vec::new() };
+            }
+        };
+        let mut levels = Vec::new();
+        for (entry, handle) in entries.zip(level_handles.iter()) {
+            match level_assets.get(handle) {
+                Some(def) => match def.resolve(registry) {
+                    Ok(_) => levels.push(CampaignLevel {
+                        path: entry.level.clone(),
+                        name: def.name.clone(),
+                        pass_score: entry.pass_score,
+                        level: handle.clone(),
+                    }),
+                    Err(e) => error!("Skipping campaign level {}. {}", entry.level, e),
+                },
+                None => error!("Skipping campaign level {}, failed to load", entry.level),
+            }
+        }
+        Self { levels }
+    }
+
+    pub fn levels(&self) -> &[CampaignLevel] {
+        &self.levels
+    }
+
+    pub fn get(&self, index: usize) -> Option<&CampaignLevel> {
+        self.levels.get(index)
+    }
+
+    /// Set up the resources to play the level at `index`, returns false (having logged why) if
+    /// the level can't be played
+    pub fn start_level(
+        &self,
+        index: usize,
+        commands: &mut Commands,
+        level_assets: &Assets<LevelDef>,
+        registry: &BallKindRegistry,
+    ) -> bool {
+        let resolved = self
+            .get(index)
+            .and_then(|level| level_assets.get(&level.level))
+            .map(|def| def.resolve(registry));
+        match resolved {
+            Some(Ok(resolved)) => {
+                level::insert_level(commands, resolved);
+                commands.insert_resource(CurrentLevel::Campaign(index));
+                true
+            }
+            Some(Err(e)) => {
+                error!("{}", e);
+                false
+            }
+            None => {
+                error!("No campaign level {}", index);
+                false
+            }
+        }
+    }
+}
+
+/// Kept in the save file
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    passed: HashSet<String>,
+    best_scores: HashMap<String, f32>,
+}
+
+impl CampaignProgress {
+    /// The first level is always open, the rest open once the level before has been passed
+    pub fn is_unlocked(&self, campaign: &Campaign, index: usize) -> bool {
+        match index.checked_sub(1) {
+            None => true,
+            Some(previous) => campaign
+                .get(previous)
+                .map(|previous| self.passed.contains(&previous.path))
+                .unwrap_or(false),
+        }
+    }
+
+    pub fn best_score(&self, level: &CampaignLevel) -> Option<f32> {
+        self.best_scores.get(&level.path).copied()
+    }
+
+    fn record(&mut self, level: &CampaignLevel, score: f32) {
+        if score >= level.pass_score {
+            self.passed.insert(level.path.clone());
+        }
+        let best = self.best_scores.entry(level.path.clone()).or_insert(score);
+        *best = best.max(score);
+    }
+}
+
+/// What's being played, so results can count towards the campaign
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurrentLevel {
+    /// Rolled from this seed
+    Random(u64),
+    /// A level passed on the command line
+    Authored,
+    Campaign(usize),
+}
+
+impl CurrentLevel {
+    /// Line for the results overlay saying whether a campaign level was passed
+    pub fn result_text(&self, campaign: &Campaign, score: f32) -> Option<String> {
+        let level = match self {
+            Self::Campaign(index) => campaign.get(*index)?,
+            Self::Random(_) | Self::Authored => return None,
+        };
+        if score >= level.pass_score {
+            Some(format!("Passed {}!", level.name))
+        } else {
+            Some(format!(
+                "Need {:.0} to pass {}",
+                level.pass_score, level.name
+            ))
+        }
+    }
+}
+
+pub fn record_result_system(
+    mut event_reader: EventReader<LevelCompleteEvent>,
+    current_level: Res<CurrentLevel>,
+    campaign: Res<Campaign>,
+    mut progress: ResMut<CampaignProgress>,
+) {
+    for event in event_reader.iter() {
+        if let CurrentLevel::Campaign(index) = *current_level {
+            if let Some(level) = campaign.get(index) {
+                progress.record(level, event.score);
+            }
+        }
+    }
+}
+
+pub struct CampaignPlugin;
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<CampaignAsset>()
+            .init_asset_loader::<CampaignLoader>();
+    }
+}
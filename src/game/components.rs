@@ -1,3 +1,5 @@
+use crate::game::timestep::{self, TICK};
+use crate::GameState;
 use bevy::prelude::*;
 use std::time::Duration;
 
@@ -13,9 +15,9 @@ impl TimedRemoval {
         }
     }
 
-    fn system(mut commands: Commands, mut query: Query<(Entity, &mut Self)>, time: Res<Time>) {
+    fn system(mut commands: Commands, mut query: Query<(Entity, &mut Self)>) {
         for (entity, mut timed_removal) in query.iter_mut() {
-            timed_removal.timer.tick(time.delta());
+            timed_removal.timer.tick(TICK);
             if timed_removal.timer.just_finished() {
                 commands.entity(entity).despawn_recursive();
             }
@@ -26,6 +28,10 @@ impl TimedRemoval {
 pub struct GeneralComponentsPlugin;
 impl Plugin for GeneralComponentsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(TimedRemoval::system);
+        // Only counting down while the level is, so nothing goes while it's paused
+        timestep::add_tick_systems(
+            app,
+            SystemSet::on_update(GameState::Game).with_system(TimedRemoval::system),
+        );
     }
 }
@@ -0,0 +1,297 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+pub const MAX_CONTAINERS: usize = 4;
+
+/// Something the player can do with a key, whichever key that is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    /// Tilt the container in this slot, counted from the left
+    TiltAnticlockwise(usize),
+    TiltClockwise(usize),
+    DoorUp,
+    DoorDown,
+    Fast,
+    Dismiss,
+    Pause,
+    ToggleHelpers,
+    DebugCountdown,
+    DebugOverlay,
+}
+
+impl Action {
+    pub fn all() -> Vec<Self> {
+        let mut actions = Vec::new();
+        for slot in 0..MAX_CONTAINERS {
+            actions.push(Self::TiltAnticlockwise(slot));
+            actions.push(Self::TiltClockwise(slot));
+        }
+        actions.extend([
+            Self::DoorUp,
+            Self::DoorDown,
+            Self::Fast,
+            Self::Dismiss,
+            Self::Pause,
+            Self::ToggleHelpers,
+            Self::DebugCountdown,
+            Self::DebugOverlay,
+        ]);
+        actions
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        // Anticlockwise and clockwise keys for the container in each slot, left to right
+        const CUP_KEYS: [(KeyCode, KeyCode); MAX_CONTAINERS] = [
+            (KeyCode::A, KeyCode::D),
+            (KeyCode::H, KeyCode::K),
+            (KeyCode::Z, KeyCode::C),
+            (KeyCode::B, KeyCode::M),
+        ];
+        match self {
+            Self::TiltAnticlockwise(slot) => {
+                CUP_KEYS.get(*slot).map(|keys| keys.0).into_iter().collect()
+            }
+            Self::TiltClockwise(slot) => {
+                CUP_KEYS.get(*slot).map(|keys| keys.1).into_iter().collect()
+            }
+            Self::DoorUp => vec![KeyCode::W],
+            Self::DoorDown => vec![KeyCode::S],
+            Self::Fast => vec![KeyCode::LShift, KeyCode::RShift],
+            Self::Dismiss => vec![KeyCode::Return],
+            Self::Pause => vec![KeyCode::Escape],
+            Self::ToggleHelpers => vec![KeyCode::Slash],
+            Self::DebugCountdown => vec![KeyCode::P],
+            Self::DebugOverlay => vec![KeyCode::I],
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TiltAnticlockwise(slot) => write!(f, "Container {} anticlockwise", slot + 1),
+            Self::TiltClockwise(slot) => write!(f, "Container {} clockwise", slot + 1),
+            Self::DoorUp => write!(f, "Jar door up"),
+            Self::DoorDown => write!(f, "Jar door down"),
+            Self::Fast => write!(f, "Tilt faster"),
+            Self::Dismiss => write!(f, "Dismiss"),
+            Self::Pause => write!(f, "Pause"),
+            Self::ToggleHelpers => write!(f, "Show key helpers"),
+            Self::DebugCountdown => write!(f, "Debug: start countdown"),
+            Self::DebugOverlay => write!(f, "Debug: toggle overlay"),
+        }
+    }
+}
+
+/// Which keys do each [Action], saved with the settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    from = "BTreeMap<Action, Vec<KeyCode>>",
+    into = "BTreeMap<Action, Vec<KeyCode>>"
+)]
+pub struct KeyBindings(BTreeMap<Action, Vec<KeyCode>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        BTreeMap::new().into()
+    }
+}
+
+// Saves from before an action existed get its default keys
+impl From<BTreeMap<Action, Vec<KeyCode>>> for KeyBindings {
+    fn from(mut keys: BTreeMap<Action, Vec<KeyCode>>) -> Self {
+        for action in Action::all() {
+            keys.entry(action).or_insert_with(|| action.default_keys());
+        }
+        Self(keys)
+    }
+}
+
+impl From<KeyBindings> for BTreeMap<Action, Vec<KeyCode>> {
+    fn from(bindings: KeyBindings) -> Self {
+        bindings.0
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The first key bound to the action, for showing in game
+    pub fn key_name(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map_or("-".to_string(), |key| format!("{:?}", key))
+    }
+
+    /// Replaces every key the action had
+    pub fn set(&mut self, action: Action, key: KeyCode) {
+        self.0.insert(action, vec![key]);
+    }
+
+    pub fn pressed(&self, input: &Input<KeyCode>, action: Action) -> bool {
+        self.keys(action).iter().any(|key| input.pressed(*key))
+    }
+
+    pub fn just_pressed(&self, input: &Input<KeyCode>, action: Action) -> bool {
+        self.keys(action).iter().any(|key| input.just_pressed(*key))
+    }
+
+    /// Other actions sharing a key with this one
+    pub fn conflicts(&self, action: Action) -> Vec<Action> {
+        let keys = self.keys(action);
+        self.0
+            .iter()
+            .filter(|(other, other_keys)| {
+                **other != action && other_keys.iter().any(|key| keys.contains(key))
+            })
+            .map(|(other, _)| *other)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Tilt {
+    Still,
+    Anticlockwise,
+    Clockwise,
+    /// From a stick, -1 (full clockwise) to 1 (full anticlockwise). Full tilt is as fast as a
+    /// fast keyboard tilt
+    Analog(f32),
+}
+
+impl Default for Tilt {
+    fn default() -> Self {
+        Self::Still
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoorMove {
+    Still,
+    Up,
+    Down,
+}
+
+impl Default for DoorMove {
+    fn default() -> Self {
+        Self::Still
+    }
+}
+
+/// Everything the player can do to the simulation in a tick. The simulation only ever reads
+/// this, never the keyboard, so it can come from a replay just as well as from a player
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ControlInput {
+    pub tilts: [Tilt; MAX_CONTAINERS],
+    pub fast: bool,
+    pub door: DoorMove,
+    /// Dismiss the overlay, held until a tick has seen it
+    pub dismiss: bool,
+}
+
+impl ControlInput {
+    pub fn tilt(&self, slot: usize) -> Tilt {
+        self.tilts.get(slot).copied().unwrap_or_default()
+    }
+}
+
+fn tilt_of_keys(input: &Input<KeyCode>, bindings: &KeyBindings, slot: usize) -> Tilt {
+    if bindings.pressed(input, Action::TiltAnticlockwise(slot)) {
+        Tilt::Anticlockwise
+    } else if bindings.pressed(input, Action::TiltClockwise(slot)) {
+        Tilt::Clockwise
+    } else {
+        Tilt::Still
+    }
+}
+
+/// Not run while a replay is playing
+pub fn keyboard_control_system(
+    input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut control: ResMut<ControlInput>,
+) {
+    for (slot, tilt) in control.tilts.iter_mut().enumerate() {
+        *tilt = tilt_of_keys(&input, &bindings, slot);
+    }
+    control.fast = bindings.pressed(&input, Action::Fast);
+    control.door = if bindings.pressed(&input, Action::DoorUp) {
+        DoorMove::Up
+    } else if bindings.pressed(&input, Action::DoorDown) {
+        DoorMove::Down
+    } else {
+        DoorMove::Still
+    };
+    // Frames can pass without a tick, so a press is kept until a tick picks it up
+    control.dismiss |= bindings.just_pressed(&input, Action::Dismiss);
+}
+
+// Stick deflection below this counts as the stick at rest
+const STICK_DEAD_ZONE: f32 = 0.15;
+// Sticks for the containers in the first two slots, tilting follows the stick's x
+const CUP_STICKS: [GamepadAxisType; 2] =
+    [GamepadAxisType::LeftStickX, GamepadAxisType::RightStickX];
+
+/// Runs after the keyboard, a stick or trigger in use takes over from the keys
+pub fn gamepad_control_system(
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut control: ResMut<ControlInput>,
+) {
+    for gamepad in gamepads.iter() {
+        let gamepad = *gamepad;
+        for (tilt, stick) in control.tilts.iter_mut().zip(CUP_STICKS.iter()) {
+            let x = axes.get(GamepadAxis(gamepad, *stick)).unwrap_or(0.0);
+            if x.abs() > STICK_DEAD_ZONE {
+                // Pushing right tips the cup right, which is clockwise. Rounded so a resting
+                // thumb doesn't fill replays with tiny changes
+                *tilt = Tilt::Analog((-x * 100.0).round() / 100.0);
+            }
+        }
+
+        let pressed = |button| buttons.pressed(GamepadButton(gamepad, button));
+        if pressed(GamepadButtonType::RightTrigger2) {
+            control.door = DoorMove::Up;
+        } else if pressed(GamepadButtonType::LeftTrigger2) {
+            control.door = DoorMove::Down;
+        }
+        control.dismiss |= buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::South));
+    }
+}
+
+/// The pause key or any gamepad's start button, which pause the game and resume it again
+pub fn pause_just_pressed(
+    input: &Input<KeyCode>,
+    bindings: &KeyBindings,
+    gamepads: &Gamepads,
+    buttons: &Input<GamepadButton>,
+) -> bool {
+    bindings.just_pressed(input, Action::Pause)
+        || gamepads
+            .iter()
+            .any(|gamepad| buttons.just_pressed(GamepadButton(*gamepad, GamepadButtonType::Start)))
+}
+
+#[test]
+fn test_key_bindings_conflicts_and_missing_actions() {
+    let mut bindings = KeyBindings::default();
+    assert!(bindings.conflicts(Action::DoorUp).is_empty());
+
+    bindings.set(Action::DoorUp, KeyCode::A);
+    assert_eq!(
+        vec![Action::TiltAnticlockwise(0)],
+        bindings.conflicts(Action::DoorUp)
+    );
+
+    // Actions missing from a save get their defaults back
+    let mut saved = BTreeMap::new();
+    saved.insert(Action::DoorUp, vec![KeyCode::Up]);
+    let loaded = KeyBindings::from(saved);
+    assert_eq!(&[KeyCode::Up], loaded.keys(Action::DoorUp));
+    assert_eq!(&[KeyCode::S], loaded.keys(Action::DoorDown));
+}
@@ -0,0 +1,130 @@
+use crate::game::balance::PartResult;
+use crate::game::control::{Action, KeyBindings};
+use crate::game::goals::LevelCriteria;
+use crate::game::level::{ContainerKind, TableLayout};
+use crate::game::BallKind;
+use crate::GameState;
+use bevy::prelude::*;
+
+pub const PLAYERS: usize = 2;
+
+/// While this resource exists two players share the table, each looking after their own
+/// ingredients
+pub struct Coop;
+
+/// Which player looks after an ingredient. The level's ingredients are dealt out in order, so
+/// with three the first player gets two
+pub fn player_for(criteria: &LevelCriteria, kind: &BallKind) -> usize {
+    criteria
+        .ingredients
+        .iter()
+        .position(|ingredient| ingredient == kind)
+        .unwrap_or(0)
+        % PLAYERS
+}
+
+/// A line for each player naming the keys for their containers
+pub fn player_key_texts(
+    criteria: &LevelCriteria,
+    layout: &TableLayout,
+    bindings: &KeyBindings,
+) -> Vec<String> {
+    (0..PLAYERS)
+        .map(|player| {
+            let keys: Vec<String> = layout
+                .containers
+                .iter()
+                .enumerate()
+                .filter(|(_, container)| player_for(criteria, &container.kind) == player)
+                .map(|(slot, container)| match container.container {
+                    ContainerKind::Cup => format!(
+                        "{}/{} {}",
+                        bindings.key_name(Action::TiltAnticlockwise(slot)),
+                        bindings.key_name(Action::TiltClockwise(slot)),
+                        container.kind
+                    ),
+                    ContainerKind::Jar => format!(
+                        "{}/{} {} jar",
+                        bindings.key_name(Action::DoorUp),
+                        bindings.key_name(Action::DoorDown),
+                        container.kind
+                    ),
+                })
+                .collect();
+            format!("Player {}: {}", player + 1, keys.join(", "))
+        })
+        .collect()
+}
+
+/// A line for each player with the score for their own ingredients, and which way each of them
+/// missed the target
+pub fn player_result_texts(criteria: &LevelCriteria, parts: &[PartResult]) -> Vec<String> {
+    (0..PLAYERS)
+        .filter_map(|player| {
+            let theirs: Vec<PartResult> = parts
+                .iter()
+                .filter(|part| player_for(criteria, &part.kind) == player)
+                .cloned()
+                .collect();
+            if theirs.is_empty() {
+                return None;
+            }
+            let misses: Vec<String> = theirs
+                .iter()
+                .map(|part| format!("{} {}", part.kind, part.miss_text()))
+                .collect();
+            Some(format!(
+                "Player {}: {:.2} ({})",
+                player + 1,
+                PartResult::combined_score(&theirs),
+                misses.join(", ")
+            ))
+        })
+        .collect()
+}
+
+/// Co-op is over once we're back on the menu
+fn stop_system(mut commands: Commands) {
+    commands.remove_resource::<Coop>();
+}
+
+pub struct CoopPlugin;
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(stop_system));
+    }
+}
+
+#[test]
+fn test_player_result_texts() {
+    use crate::game::balance::BalanceCounter;
+    use crate::game::goals::{Mix, MixMeasure};
+    use crate::game::ingredients::test_kind;
+
+    let (a, b, c) = (test_kind("A"), test_kind("B"), test_kind("C"));
+    let criteria = LevelCriteria {
+        ingredients: vec![a.clone(), b.clone(), c.clone()],
+        min_weight: 5.0,
+        target_mix: Mix::new(vec![(a.clone(), 50), (b.clone(), 30), (c.clone(), 20)]),
+        mix_measure: MixMeasure::Count,
+        countdown_time_secs: 10.0,
+    };
+    assert_eq!(0, player_for(&criteria, &a));
+    assert_eq!(1, player_for(&criteria, &b));
+    assert_eq!(0, player_for(&criteria, &c));
+
+    let mut balance = BalanceCounter::new();
+    for (kind, count) in [(a, 5), (b, 4), (c, 1)] {
+        for _ in 0..count {
+            balance.incr(kind.clone());
+        }
+    }
+    let parts = balance.part_results(&criteria.target_mix, criteria.mix_measure);
+    assert_eq!(
+        vec![
+            "Player 1: 83.10 (A on target, C under by 10.00)".to_string(),
+            "Player 2: 66.21 (B over by 10.00)".to_string(),
+        ],
+        player_result_texts(&criteria, &parts)
+    );
+}
@@ -1,44 +1,40 @@
 use crate::game::ball::BallKind;
+use crate::game::control::{Action, ControlInput, KeyBindings, Tilt};
+use crate::game::level::{ContainerKind, TableLayout};
 use crate::game::not_a_cup::spawn_jar;
+use crate::game::table::Table;
+use crate::game::versus::{self, Versus};
 use crate::game::GameOnlyMarker;
+use crate::manifest::Sprites;
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use bevy_rapier2d::dynamics::{Ccd, LockedAxes, RigidBody, Sleeping, Velocity};
 use bevy_rapier2d::geometry::{Collider, CollisionGroups};
-use rand::Rng;
 use std::time::Duration;
 
-fn angvel_of_input(input: &Input<KeyCode>, ac: KeyCode, c: KeyCode, fast: bool) -> f32 {
-    let v = if input.pressed(ac) {
-        1.0
-    } else if input.pressed(c) {
-        -1.0
-    } else {
-        0.0
-    };
-    if fast {
-        v * 2.5
-    } else {
-        v
+const TILT_SPEED: f32 = 1.0;
+const FAST_TILT_SPEED: f32 = 2.5;
+
+fn angvel_of_tilt(tilt: Tilt, fast: bool) -> f32 {
+    let speed = if fast { FAST_TILT_SPEED } else { TILT_SPEED };
+    match tilt {
+        Tilt::Anticlockwise => speed,
+        Tilt::Clockwise => -speed,
+        Tilt::Still => 0.0,
+        Tilt::Analog(v) => v.clamp(-1.0, 1.0) * FAST_TILT_SPEED,
     }
 }
 
-pub fn rotate_cup_system(mut cup_query: Query<(&mut Velocity, &Cup)>, input: Res<Input<KeyCode>>) {
-    let fast = input.pressed(KeyCode::LShift) || input.pressed(KeyCode::RShift);
-    let ang_vel_0 = angvel_of_input(&input, KeyCode::A, KeyCode::D, fast);
-    let ang_vel_1 = angvel_of_input(&input, KeyCode::H, KeyCode::K, fast);
-
+pub fn rotate_cup_system(mut cup_query: Query<(&mut Velocity, &Cup)>, control: Res<ControlInput>) {
     for (mut velocity, cup) in cup_query.iter_mut() {
-        match cup.0 {
-            BallKind::Blue => velocity.angvel = ang_vel_0,
-            BallKind::Red => velocity.angvel = ang_vel_1,
-        }
+        velocity.angvel = angvel_of_tilt(control.tilt(cup.slot), control.fast);
     }
 }
 
 pub fn ui_helper_show_system(
     mut query: Query<(&mut Visibility, &mut CupUiHelper)>,
     input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
     time: Res<Time>,
 ) {
     for (mut vis, mut helper) in query.iter_mut() {
@@ -47,7 +43,7 @@ pub fn ui_helper_show_system(
             vis.is_visible = false;
         }
     }
-    if input.just_pressed(KeyCode::Slash) {
+    if bindings.just_pressed(&input, Action::ToggleHelpers) {
         for (mut vis, mut helper) in query.iter_mut() {
             vis.is_visible = !vis.is_visible;
             helper.0.pause();
@@ -56,25 +52,41 @@ pub fn ui_helper_show_system(
 }
 
 #[derive(Component)]
-pub struct Cup(pub BallKind);
+pub struct Cup {
+    pub kind: BallKind,
+    pub slot: usize,
+}
 
 #[derive(Component)]
 pub struct CupUiHelper(pub Timer);
 
-pub fn spawn_cups(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let mut rng = rand::thread_rng();
-    let spawn_jar_instead = rng.gen_bool(0.25);
-    if spawn_jar_instead {
-        spawn_jar(-50.0, BallKind::Blue, &mut commands, &asset_server);
-    } else {
-        spawn_cup(-50.0, BallKind::Blue, &mut commands, &asset_server);
+/// Every table gets the layout's containers. Later tables' cups take the slots after the first
+/// table's, so each player has their own keys
+pub fn spawn_cups(
+    mut commands: Commands,
+    sprites: Res<Sprites>,
+    layout: Res<TableLayout>,
+    versus: Option<Res<Versus>>,
+) {
+    let offsets = Table::offsets(versus::table_count(versus.as_deref()));
+    for (table, offset) in offsets.into_iter().enumerate() {
+        for (index, container) in layout.containers.iter().enumerate() {
+            let slot = table * layout.containers.len() + index;
+            let x = container.x + offset;
+            let ball_kind = container.kind.clone();
+            match container.container {
+                ContainerKind::Cup => {
+                    spawn_cup(x, slot, Table(table), ball_kind, &mut commands, &sprites)
+                }
+                ContainerKind::Jar => spawn_jar(x, ball_kind, &mut commands, &sprites),
+            }
+        }
     }
-    spawn_cup(50.0, BallKind::Red, &mut commands, &asset_server);
-    spawn_centre_ui_helper(&mut commands, &asset_server);
+    spawn_centre_ui_helper(&mut commands, &sprites);
 }
 
-fn spawn_centre_ui_helper(commands: &mut Commands, asset_server: &Res<AssetServer>) {
-    let texture = asset_server.load("ui_helper_2.png");
+fn spawn_centre_ui_helper(commands: &mut Commands, sprites: &Sprites) {
+    let texture = sprites.helper_centre.clone();
     let transform = Transform::from_xyz(0.0, -20.0, 0.0);
 
     let mut timer = Timer::new(Duration::from_secs(7), false);
@@ -96,19 +108,19 @@ fn spawn_centre_ui_helper(commands: &mut Commands, asset_server: &Res<AssetServe
 
 fn spawn_cup(
     x_offset: f32,
+    slot: usize,
+    table: Table,
     ball_kind: BallKind,
     commands: &mut Commands,
-    asset_server: &AssetServer,
+    sprites: &Sprites,
 ) {
-    let sprite_tex = asset_server.load("cup.png");
+    let sprite_tex = sprites.cup.clone();
 
-    let ui_helper_tex = {
-        let fname = if x_offset < 0.0 {
-            "ui_helper_l.png"
-        } else {
-            "ui_helper_r.png"
-        };
-        asset_server.load(fname)
+    // Only the first two slots' keys have helper art
+    let ui_helper_tex = match slot {
+        0 => Some(sprites.helper_left.clone()),
+        1 => Some(sprites.helper_right.clone()),
+        _ => None,
     };
     let shape = {
         let thickness = 4.0;
@@ -147,22 +159,28 @@ fn spawn_cup(
             transform,
             ..default()
         })
-        .insert(Cup(ball_kind));
+        .insert(table)
+        .insert(Cup {
+            kind: ball_kind,
+            slot,
+        });
 
-    ui_transform.translation.y += 60.0;
-    let mut timer = Timer::new(Duration::from_secs(7), false);
-    timer.pause();
-    commands
-        .spawn()
-        .insert_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: Color::rgba(1.0, 1.0, 1.0, 0.7),
+    if let Some(ui_helper_tex) = ui_helper_tex {
+        ui_transform.translation.y += 60.0;
+        let mut timer = Timer::new(Duration::from_secs(7), false);
+        timer.pause();
+        commands
+            .spawn()
+            .insert_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.7),
+                    ..default()
+                },
+                texture: ui_helper_tex,
+                transform: ui_transform,
                 ..default()
-            },
-            texture: ui_helper_tex,
-            transform: ui_transform,
-            ..default()
-        })
-        .insert(GameOnlyMarker)
-        .insert(CupUiHelper(timer));
+            })
+            .insert(GameOnlyMarker)
+            .insert(CupUiHelper(timer));
+    }
 }
@@ -1,14 +1,27 @@
 use crate::game::audio::AudioTriggerEvent;
-use crate::game::balance::BalanceCounter;
+use crate::game::balance::{BalanceCounter, PartResult};
+use crate::game::bot::Demo;
+use crate::game::campaign::{Campaign, CurrentLevel};
+use crate::game::control::{Action, KeyBindings};
+use crate::game::coop::{self, Coop};
 use crate::game::overlay::Overlay;
 
-use crate::game::ball::{BallKind, SpawnBallEvent};
+use crate::game::ball::{BallKind, BallsSpawned, SpawnBallEvent};
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level::{self, TableLayout};
+use crate::game::seed::LevelRng;
+use crate::game::table::Table;
+use crate::game::timestep::TICK;
+use crate::game::versus::{self, Versus};
+use crate::manifest::Fonts;
 use crate::TotalScore;
 use bevy::prelude::*;
 use bevy::time::Stopwatch;
 use rand::distributions::Standard;
 use rand::prelude::Distribution;
+use rand::seq::SliceRandom;
 use rand::Rng;
+use serde::Deserialize;
 use std::fmt::{Display, Formatter};
 use std::time::Duration;
 
@@ -31,23 +44,57 @@ impl Distribution<ProtoMix> for Standard {
     }
 }
 
-pub enum Mix {
-    FiftyFifty,
-    AB {
-        a_pct: usize,
-        a_kind: BallKind,
-        b_kind: BallKind,
-    },
+enum ProtoThreeWayMix {
+    Thirds,
+    FiftyThirtyTwenty,
+    RandomOther,
+}
+
+impl Distribution<ProtoThreeWayMix> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ProtoThreeWayMix {
+        match rng.gen_range(0..=4) {
+            0 | 1 => ProtoThreeWayMix::Thirds,
+            2 | 3 => ProtoThreeWayMix::FiftyThirtyTwenty,
+            4 => ProtoThreeWayMix::RandomOther,
+            _ => panic!("BUG: Generated outside of estabished range"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MixPart {
+    pub kind: BallKind,
+    pub pct: usize,
+}
+
+/// A target recipe, the percentage of each ingredient in the final mix. Percentages add up to 100
+#[derive(Debug, Clone)]
+pub struct Mix {
+    parts: Vec<MixPart>,
 }
 impl Mix {
+    pub fn new(parts: Vec<(BallKind, usize)>) -> Self {
+        let parts: Vec<MixPart> = parts
+            .into_iter()
+            .map(|(kind, pct)| MixPart { kind, pct })
+            .collect();
+        debug_assert_eq!(100, parts.iter().map(|part| part.pct).sum::<usize>());
+        Self { parts }
+    }
+
+    pub fn parts(&self) -> &[MixPart] {
+        &self.parts
+    }
+
     pub fn to_string_hum(&self) -> String {
-        match self {
-            Self::FiftyFifty => String::from("50/50"),
-            Self::AB {
-                a_pct,
-                a_kind,
-                b_kind,
-            } => format!("{}pct {} to {}pct {}", a_pct, a_kind, 100 - a_pct, b_kind),
+        match self.parts.as_slice() {
+            [a, b] if a.pct == 50 && b.pct == 50 => format!("50/50 {} and {}", a.kind, b.kind),
+            [a, b] => format!("{}pct {} to {}pct {}", a.pct, a.kind, b.pct, b.kind),
+            parts => parts
+                .iter()
+                .map(|part| format!("{}pct {}", part.pct, part.kind))
+                .collect::<Vec<String>>()
+                .join(" / "),
         }
     }
 }
@@ -57,23 +104,48 @@ impl Display for Mix {
     }
 }
 
+/// What the percentages in a [Mix] are measured against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MixMeasure {
+    Count,
+    Weight,
+}
+impl Display for MixMeasure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Count => write!(f, "by count"),
+            Self::Weight => write!(f, "by weight"),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct LevelCriteria {
+    /// Ingredients on the table, one container each from left to right
+    pub ingredients: Vec<BallKind>,
     pub min_weight: f32,
     pub target_mix: Mix,
+    pub mix_measure: MixMeasure,
     pub countdown_time_secs: f32,
 }
 
 pub fn initial_goal_display(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    fonts: Res<Fonts>,
     //overlay_query: Query<Entity, With<Overlay>>,
-    level_stopwatch: ResMut<LevelStopwatch>,
+    mut level_stopwatch: ResMut<LevelStopwatch>,
     criteria: Res<LevelCriteria>,
+    layout: Res<TableLayout>,
+    bindings: Res<KeyBindings>,
+    versus: Option<Res<Versus>>,
 ) {
-    let display_texts = vec![
+    let mut display_texts = vec![
         "Goals".into(),
         "-".into(),
-        format!("Make a mix of {:.2}", criteria.target_mix),
+        format!(
+            "Make a mix of {:.2} {}",
+            criteria.target_mix, criteria.mix_measure
+        ),
         format!("Minimum weight of: {:.2}", criteria.min_weight),
         format!(
             "You get {:.0}s once you hit this weight to get it right",
@@ -81,150 +153,249 @@ pub fn initial_goal_display(
         ),
         "(Enter to dismiss)".into(),
     ];
+    if versus.is_some() {
+        // Each player has the keys for the containers on their table
+        let containers = layout.containers.len();
+        for player in 0..versus::PLAYERS {
+            let keys: Vec<String> = (player * containers..(player + 1) * containers)
+                .map(|slot| {
+                    format!(
+                        "{}/{}",
+                        bindings.key_name(Action::TiltAnticlockwise(slot)),
+                        bindings.key_name(Action::TiltClockwise(slot))
+                    )
+                })
+                .collect();
+            display_texts.push(format!("Player {}: {}", player + 1, keys.join(", ")));
+        }
+    }
 
     let text_style = TextStyle {
-        font: asset_server.load("Quicksand-Regular.ttf"),
+        font: fonts.regular.clone(),
         font_size: 20.0,
         color: Default::default(),
     };
-    super::overlay::spawn(&mut commands, text_style, display_texts, level_stopwatch);
+    super::overlay::spawn(
+        &mut commands,
+        text_style,
+        display_texts,
+        &mut level_stopwatch,
+    );
 }
 
-fn final_calculation_display(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    level_stopwatch: ResMut<LevelStopwatch>,
-    a_result: String,
-    b_result: String,
-    score: f32,
-) {
-    let display_texts = vec![
-        "Result".into(),
-        "".into(),
-        a_result,
-        b_result,
-        format!("Score: {:.2}", score),
-    ];
+/// How the level went, put in once its countdown is over for the results screen to show
+pub struct LevelResults {
+    /// Just the one unless it's versus
+    pub tables: Vec<TableResults>,
+    /// Lines that depend on how the level was played, like each co-op player's score, who won
+    /// versus or whether a campaign level was passed
+    pub notes: Vec<String>,
+    pub elapsed_secs: f32,
+}
 
-    let text_style = TextStyle {
-        font: asset_server.load("Quicksand-Regular.ttf"),
-        font_size: 20.0,
-        color: Default::default(),
-    };
-    super::overlay::spawn(&mut commands, text_style, display_texts, level_stopwatch);
+/// How one table's mix turned out. In versus it's kept on the table's balance until every
+/// table has one, see [versus::results_system](crate::game::versus)
+#[derive(Component, Clone)]
+pub struct TableResults {
+    /// What the score is, e.g. "Team score" or which player's table it was
+    pub label: String,
+    pub parts: Vec<PartResult>,
+    pub score: f32,
+    pub balls_spawned: usize,
+    pub balls_weighed: usize,
 }
 
 impl LevelCriteria {
     pub fn to_strings(&self) -> Vec<String> {
         vec![
             format!("Min Weight: {:.2}", self.min_weight),
-            format!("Target Mix: {}", self.target_mix),
+            format!("Target Mix: {} {}", self.target_mix, self.mix_measure),
         ]
     }
 
+    /// Each table's countdown runs on its own
     pub fn watch_system(
         criteria: Res<Self>,
         mut level_stopwatch: ResMut<LevelStopwatch>,
-        mut countdown: ResMut<Countdown>,
-        balance_counter: Res<BalanceCounter>,
+        mut tables: Query<(Entity, &Table, &BalanceCounter, &mut Countdown)>,
         mut audio_trigger_event_writer: EventWriter<AudioTriggerEvent>,
         mut commands: Commands,
-        asset_server: Res<AssetServer>,
+        balls_spawned: Res<BallsSpawned>,
         mut total_score: ResMut<TotalScore>,
+        mut level_complete_event_writer: EventWriter<LevelCompleteEvent>,
+        current_level: Res<CurrentLevel>,
+        campaign: Res<Campaign>,
+        demo: Option<Res<Demo>>,
+        versus: Option<Res<Versus>>,
+        coop: Option<Res<Coop>>,
     ) {
-        let result: CriteriaResult = match *countdown {
-            Countdown::Inactive => {
-                if balance_counter.total_weight() > criteria.min_weight {
-                    CriteriaResult::StartCountdown
-                } else {
-                    CriteriaResult::Nothing
+        for (entity, table, balance_counter, mut countdown) in tables.iter_mut() {
+            let result: CriteriaResult = match *countdown {
+                Countdown::Inactive => {
+                    if balance_counter.total_weight() > criteria.min_weight {
+                        CriteriaResult::StartCountdown
+                    } else {
+                        CriteriaResult::Nothing
+                    }
                 }
-            }
-            Countdown::Active {
-                end,
-                end_calculated,
-            } => {
-                if level_stopwatch.stopwatch.elapsed_secs() > end && !end_calculated {
-                    CriteriaResult::CalculateResult
-                } else {
-                    CriteriaResult::Nothing
+                Countdown::Active {
+                    end,
+                    end_calculated,
+                } => {
+                    if level_stopwatch.stopwatch.elapsed_secs() > end && !end_calculated {
+                        CriteriaResult::CalculateResult
+                    } else {
+                        CriteriaResult::Nothing
+                    }
                 }
-            }
-        };
+            };
 
-        match result {
-            CriteriaResult::StartCountdown => {
-                *countdown = Countdown::Active {
-                    end: level_stopwatch.stopwatch.elapsed_secs() + criteria.countdown_time_secs,
-                    end_calculated: false,
-                };
-                audio_trigger_event_writer.send(AudioTriggerEvent::CountdownStarted);
-            }
-            CriteriaResult::CalculateResult => {
-                level_stopwatch.stop();
-                let (a_result, b_result, score) =
-                    balance_counter.ratios_and_score(&criteria.target_mix);
-                final_calculation_display(
-                    commands,
-                    asset_server,
-                    level_stopwatch,
-                    a_result,
-                    b_result,
-                    score,
-                );
-                total_score.add_score(score);
-                countdown.set_end_calculated();
-            }
-            CriteriaResult::Nothing => (),
-        };
+            match result {
+                CriteriaResult::StartCountdown => {
+                    *countdown = Countdown::Active {
+                        end: level_stopwatch.stopwatch.elapsed_secs()
+                            + criteria.countdown_time_secs,
+                        end_calculated: false,
+                    };
+                    audio_trigger_event_writer.send(AudioTriggerEvent::CountdownStarted);
+                }
+                CriteriaResult::CalculateResult => {
+                    countdown.set_end_calculated();
+                    let parts =
+                        balance_counter.part_results(&criteria.target_mix, criteria.mix_measure);
+                    let score = PartResult::combined_score(&parts);
+                    let mut notes = Vec::new();
+                    let label = if versus.is_some() {
+                        format!("Player {}", table.0 + 1)
+                    } else if coop.is_some() {
+                        notes.extend(coop::player_result_texts(&criteria, &parts));
+                        "Team score".to_string()
+                    } else {
+                        "Score".to_string()
+                    };
+                    let table_results = TableResults {
+                        label,
+                        parts,
+                        score,
+                        balls_spawned: balls_spawned.get(*table),
+                        balls_weighed: balance_counter.total_count(),
+                    };
+                    if versus.is_some() {
+                        // Shown once every table's done, see versus::results_system
+                        commands.entity(entity).insert(table_results);
+                        continue;
+                    }
+                    level_stopwatch.stop();
+                    notes.extend(current_level.result_text(&campaign, score));
+                    commands.insert_resource(LevelResults {
+                        tables: vec![table_results],
+                        notes,
+                        elapsed_secs: level_stopwatch.stopwatch.elapsed_secs(),
+                    });
+                    // The menu's demo levels aren't the player's
+                    if demo.is_none() {
+                        total_score.add_score(score);
+                    }
+                    level_complete_event_writer.send(LevelCompleteEvent { score });
+                }
+                CriteriaResult::Nothing => (),
+            };
+        }
     }
 
-    pub fn new_random() -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn new_random<R: Rng>(registry: &BallKindRegistry, rng: &mut R) -> Self {
+        let ingredient_count = if registry.kinds().len() >= 3 && rng.gen_bool(0.25) {
+            3
+        } else {
+            2
+        };
+        let ingredients: Vec<BallKind> = registry
+            .kinds()
+            .choose_multiple(rng, ingredient_count)
+            .cloned()
+            .collect();
 
         let weight_bounds = 1.2f32..6.0f32;
         let min_weight = rng.gen_range(weight_bounds);
-        let target_mix = {
-            let proto_mix: ProtoMix = rng.gen();
-            let left = rng.gen_bool(0.5);
-            match proto_mix {
-                ProtoMix::FiftyFifty => Mix::FiftyFifty,
-                ProtoMix::FixedQuarter => {
-                    let a_pct = if left { 25usize } else { 75usize };
-                    Mix::AB {
-                        a_pct,
-                        a_kind: BallKind::Blue,
-                        b_kind: BallKind::Red,
-                    }
-                }
-                ProtoMix::FixedThird => {
-                    let a_pct = if left { 33usize } else { 66usize };
-                    Mix::AB {
-                        a_pct,
-                        a_kind: BallKind::Blue,
-                        b_kind: BallKind::Red,
-                    }
-                }
-                ProtoMix::RandomOther => {
-                    let a_pct = rng.gen_range(10..90);
-                    Mix::AB {
-                        a_pct,
-                        a_kind: BallKind::Blue,
-                        b_kind: BallKind::Red,
-                    }
-                }
-            }
+        let target_mix = match ingredients.as_slice() {
+            [a_kind, b_kind] => Self::random_two_way_mix(rng, a_kind, b_kind),
+            _ => Self::random_three_way_mix(rng, &ingredients),
+        };
+        let mix_measure = if rng.gen_bool(0.3) {
+            MixMeasure::Weight
+        } else {
+            MixMeasure::Count
         };
         let countdown_time_secs = rng.gen_range(5f32..15f32).round();
 
         Self {
+            ingredients,
             min_weight,
             target_mix,
+            mix_measure,
             countdown_time_secs,
         }
     }
 }
 
+/// Rolls the first random level once the ball kinds are known (unless a level was loaded
+/// already), later levels are rolled as they're requested
+pub fn init_level_criteria(
+    mut commands: Commands,
+    registry: Res<BallKindRegistry>,
+    mut level_rng: ResMut<LevelRng>,
+    criteria: Option<Res<LevelCriteria>>,
+) {
+    if criteria.is_none() {
+        level::insert_random_level(&mut commands, &registry, &mut level_rng);
+    }
+}
+
+impl LevelCriteria {
+    fn random_two_way_mix<R: Rng>(rng: &mut R, a_kind: &BallKind, b_kind: &BallKind) -> Mix {
+        let proto_mix: ProtoMix = rng.gen();
+        let left = rng.gen_bool(0.5);
+        let a_pct = match proto_mix {
+            ProtoMix::FiftyFifty => 50usize,
+            ProtoMix::FixedQuarter => {
+                if left {
+                    25usize
+                } else {
+                    75usize
+                }
+            }
+            ProtoMix::FixedThird => {
+                if left {
+                    33usize
+                } else {
+                    66usize
+                }
+            }
+            ProtoMix::RandomOther => rng.gen_range(10..90),
+        };
+        Mix::new(vec![(a_kind.clone(), a_pct), (b_kind.clone(), 100 - a_pct)])
+    }
+
+    fn random_three_way_mix<R: Rng>(rng: &mut R, ingredients: &[BallKind]) -> Mix {
+        let proto_mix: ProtoThreeWayMix = rng.gen();
+        let mut pcts = match proto_mix {
+            ProtoThreeWayMix::Thirds => vec![34usize, 33, 33],
+            ProtoThreeWayMix::FiftyThirtyTwenty => vec![50usize, 30, 20],
+            ProtoThreeWayMix::RandomOther => {
+                let a_pct = rng.gen_range(10..80);
+                let b_pct = rng.gen_range(10..(90 - a_pct));
+                vec![a_pct, b_pct, 100 - a_pct - b_pct]
+            }
+        };
+        pcts.shuffle(rng);
+        Mix::new(ingredients.iter().cloned().zip(pcts.into_iter()).collect())
+    }
+}
+
+pub struct LevelCompleteEvent {
+    pub score: f32,
+}
+
 enum CriteriaResult {
     StartCountdown,
     CalculateResult,
@@ -252,11 +423,10 @@ impl LevelStopwatch {
     }
     pub fn update_system(
         mut stopwatch: ResMut<Self>,
-        time: Res<Time>,
         mut ball_spawn_event_writer: EventWriter<SpawnBallEvent>,
     ) {
-        stopwatch.stopwatch.tick(time.delta());
-        stopwatch.timer.tick(time.delta());
+        stopwatch.stopwatch.tick(TICK);
+        stopwatch.timer.tick(TICK);
         if stopwatch.timer.just_finished() {
             ball_spawn_event_writer.send(SpawnBallEvent);
         }
@@ -275,6 +445,9 @@ impl LevelStopwatch {
             self.timer.unpause();
         }
     }
+    pub fn set_spawn_interval(&mut self, secs: f32) {
+        self.timer.set_duration(Duration::from_secs_f32(secs));
+    }
     pub fn reset(&mut self) {
         self.stopwatch.reset();
         self.timer.reset();
@@ -287,16 +460,14 @@ impl LevelStopwatch {
     }
 }
 
+/// Kept on each table's balance
+#[derive(Component)]
 pub enum Countdown {
     Inactive,
     Active { end: f32, end_calculated: bool },
 }
 
 impl Countdown {
-    pub fn reset(&mut self) {
-        *self = Self::Inactive
-    }
-
     fn set_end_calculated(&mut self) {
         let mut end_calc = match self {
             Self::Active { end_calculated, .. } => Some(end_calculated),
@@ -309,29 +480,33 @@ impl Countdown {
 }
 
 pub fn debug_countdown_trigger_system(
-    mut countdown: ResMut<Countdown>,
+    mut countdowns: Query<&mut Countdown>,
     stopwatch: Res<LevelStopwatch>,
     level_criteria: Res<LevelCriteria>,
     input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
 ) {
-    if input.just_pressed(KeyCode::P) {
-        *countdown = Countdown::Active {
-            end: stopwatch.stopwatch.elapsed_secs() + level_criteria.countdown_time_secs,
-            end_calculated: false,
+    if bindings.just_pressed(&input, Action::DebugCountdown) {
+        for mut countdown in countdowns.iter_mut() {
+            *countdown = Countdown::Active {
+                end: stopwatch.stopwatch.elapsed_secs() + level_criteria.countdown_time_secs,
+                end_calculated: false,
+            }
         }
     }
 }
 pub fn debug_overlay_system(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    fonts: Res<Fonts>,
     overlay_query: Query<Entity, With<Overlay>>,
     input: Res<Input<KeyCode>>,
-    level_stopwatch: ResMut<LevelStopwatch>,
+    bindings: Res<KeyBindings>,
+    mut level_stopwatch: ResMut<LevelStopwatch>,
 ) {
-    if input.just_pressed(KeyCode::I) {
+    if bindings.just_pressed(&input, Action::DebugOverlay) {
         if overlay_query.is_empty() {
             let text_style = TextStyle {
-                font: asset_server.load("Quicksand-Regular.ttf"),
+                font: fonts.regular.clone(),
                 font_size: 20.0,
                 color: Default::default(),
             };
@@ -339,7 +514,7 @@ pub fn debug_overlay_system(
                 &mut commands,
                 text_style,
                 vec!["Overlay", "Bro", "You have been warned"],
-                level_stopwatch,
+                &mut level_stopwatch,
             );
         } else {
             super::overlay::despawn(&mut commands, &overlay_query);
@@ -0,0 +1,138 @@
+use crate::game::ball::BallKind;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+pub const DEFAULT_INGREDIENTS_PATH: &str = "ingredients/default.ingredients.ron";
+
+/// Everything needed to spawn and weigh one kind of ball, as authored in an ingredients file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BallKindDef {
+    pub name: String,
+    pub colour: (f32, f32, f32),
+    pub radius: f32,
+    pub mass: f32,
+    pub sprite: String,
+}
+
+/// A kind for tests, only its name matters
+#[cfg(test)]
+pub fn test_kind(name: &str) -> BallKind {
+    BallKind::from_def(&BallKindDef {
+        name: name.into(),
+        colour: (1.0, 1.0, 1.0),
+        radius: 2.8,
+        mass: 1.0,
+        sprite: "ball.png".into(),
+    })
+}
+
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "6e1adb8e-e18e-445a-a6bf-36008d2d5d57"]
+pub struct IngredientsAsset {
+    pub kinds: Vec<BallKindDef>,
+}
+
+#[derive(Debug)]
+pub struct IngredientsError {
+    field: String,
+    reason: String,
+}
+
+impl Display for IngredientsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid ingredients, {}: {}", self.field, self.reason)
+    }
+}
+
+impl std::error::Error for IngredientsError {}
+
+impl IngredientsAsset {
+    /// Parse and validate an ingredients file, for when it's not going through the asset server
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        let asset: Self = ron::de::from_bytes(bytes)?;
+        asset.validate()?;
+        Ok(asset)
+    }
+
+    fn validate(&self) -> Result<(), IngredientsError> {
+        let error = |field: String, reason: &str| IngredientsError {
+            field,
+            reason: reason.into(),
+        };
+        if self.kinds.len() < 2 {
+            return Err(error("kinds".into(), "need at least two ingredients"));
+        }
+        let mut names = HashSet::new();
+        for (i, kind) in self.kinds.iter().enumerate() {
+            if kind.name.is_empty() {
+                return Err(error(format!("kinds[{}].name", i), "must not be empty"));
+            }
+            if !names.insert(kind.name.as_str()) {
+                return Err(error(format!("kinds[{}].name", i), "duplicate name"));
+            }
+            if kind.radius <= 0.0 {
+                return Err(error(format!("kinds[{}].radius", i), "must be positive"));
+            }
+            if kind.mass <= 0.0 {
+                return Err(error(format!("kinds[{}].mass", i), "must be positive"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct IngredientsLoader;
+
+impl AssetLoader for IngredientsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let asset = IngredientsAsset::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ingredients.ron"]
+    }
+}
+
+/// The ball kinds available to levels, in the order they were authored.
+#[derive(Debug, Clone)]
+pub struct BallKindRegistry {
+    kinds: Vec<BallKind>,
+}
+
+impl BallKindRegistry {
+    pub fn from_asset(asset: &IngredientsAsset) -> Self {
+        Self {
+            kinds: asset.kinds.iter().map(BallKind::from_def).collect(),
+        }
+    }
+
+    pub fn kinds(&self) -> &[BallKind] {
+        &self.kinds
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BallKind> {
+        self.kinds.iter().find(|kind| kind.name() == name)
+    }
+}
+
+pub struct IngredientsPlugin;
+impl Plugin for IngredientsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<IngredientsAsset>()
+            .init_asset_loader::<IngredientsLoader>();
+    }
+}
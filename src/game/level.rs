@@ -0,0 +1,432 @@
+use crate::game::ball::BallKind;
+use crate::game::campaign::CurrentLevel;
+use crate::game::control::MAX_CONTAINERS;
+use crate::game::goals::{LevelCriteria, Mix, MixMeasure};
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::seed::{self, LevelRng};
+use crate::game::versus::{self, Versus};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use rand::Rng;
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+
+const DEFAULT_BALL_SPAWN_SECS: f32 = 2.0;
+const DEFAULT_BALANCE_POSITION: (f32, f32) = (0.0, -70.0);
+const DEFAULT_TABLE_POSITION: (f32, f32) = (0.0, -160.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ContainerKind {
+    Cup,
+    Jar,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContainerDef {
+    pub ingredient: String,
+    pub container: ContainerKind,
+    pub x: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CriteriaDef {
+    pub min_weight: f32,
+    pub target_mix: Vec<(String, usize)>,
+    #[serde(default = "default_mix_measure")]
+    pub mix_measure: MixMeasure,
+    pub countdown_secs: f32,
+}
+
+fn default_mix_measure() -> MixMeasure {
+    MixMeasure::Count
+}
+fn default_ball_spawn_secs() -> f32 {
+    DEFAULT_BALL_SPAWN_SECS
+}
+fn default_balance_position() -> (f32, f32) {
+    DEFAULT_BALANCE_POSITION
+}
+fn default_table_position() -> (f32, f32) {
+    DEFAULT_TABLE_POSITION
+}
+
+/// A hand authored level, loaded from a `.level.ron` file
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "b36cc8d9-0d9a-4200-bd07-74b042573930"]
+pub struct LevelDef {
+    pub name: String,
+    pub criteria: CriteriaDef,
+    pub containers: Vec<ContainerDef>,
+    #[serde(default = "default_ball_spawn_secs")]
+    pub ball_spawn_secs: f32,
+    #[serde(default = "default_balance_position")]
+    pub balance: (f32, f32),
+    #[serde(default = "default_table_position")]
+    pub table: (f32, f32),
+}
+
+#[derive(Debug)]
+pub struct LevelError {
+    field: String,
+    reason: String,
+}
+
+impl LevelError {
+    fn new(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Display for LevelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid level, {}: {}", self.field, self.reason)
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl LevelDef {
+    /// Parse and validate a level file, for when it's not going through the asset server
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        let level: Self = ron::de::from_bytes(bytes)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    /// Checks everything that can be checked without knowing the available ingredients
+    fn validate(&self) -> Result<(), LevelError> {
+        let criteria = &self.criteria;
+        if criteria.min_weight <= 0.0 {
+            return Err(LevelError::new("criteria.min_weight", "must be positive"));
+        }
+        if criteria.countdown_secs <= 0.0 {
+            return Err(LevelError::new(
+                "criteria.countdown_secs",
+                "must be positive",
+            ));
+        }
+        if criteria.target_mix.is_empty() {
+            return Err(LevelError::new("criteria.target_mix", "must not be empty"));
+        }
+        for (i, (name, pct)) in criteria.target_mix.iter().enumerate() {
+            if *pct == 0 {
+                return Err(LevelError::new(
+                    format!("criteria.target_mix[{}]", i),
+                    "percentage must be positive",
+                ));
+            }
+            if criteria.target_mix[..i]
+                .iter()
+                .any(|(other, _)| other == name)
+            {
+                return Err(LevelError::new(
+                    format!("criteria.target_mix[{}]", i),
+                    format!("{} is already in the mix", name),
+                ));
+            }
+            if !self.containers.iter().any(|c| c.ingredient == *name) {
+                return Err(LevelError::new(
+                    format!("criteria.target_mix[{}]", i),
+                    format!("no container holds {}", name),
+                ));
+            }
+        }
+        let total_pct: usize = criteria.target_mix.iter().map(|(_, pct)| pct).sum();
+        if total_pct != 100 {
+            return Err(LevelError::new(
+                "criteria.target_mix",
+                format!("percentages add up to {}, not 100", total_pct),
+            ));
+        }
+        if self.containers.is_empty() {
+            return Err(LevelError::new("containers", "must not be empty"));
+        }
+        if self.containers.len() > MAX_CONTAINERS {
+            return Err(LevelError::new(
+                "containers",
+                format!("at most {} containers are supported", MAX_CONTAINERS),
+            ));
+        }
+        if self.ball_spawn_secs <= 0.0 {
+            return Err(LevelError::new("ball_spawn_secs", "must be positive"));
+        }
+        Ok(())
+    }
+
+    /// Turn the level into the resources the game runs on, looking up ingredients by name
+    pub fn resolve(
+        &self,
+        registry: &BallKindRegistry,
+    ) -> Result<(LevelCriteria, TableLayout), LevelError> {
+        self.validate()?;
+
+        let lookup = |field: String, name: &str| {
+            registry
+                .get(name)
+                .cloned()
+                .ok_or_else(|| LevelError::new(field, format!("unknown ingredient {}", name)))
+        };
+
+        let mut containers = Vec::new();
+        for (i, container) in self.containers.iter().enumerate() {
+            containers.push(ContainerLayout {
+                kind: lookup(
+                    format!("containers[{}].ingredient", i),
+                    &container.ingredient,
+                )?,
+                container: container.container,
+                x: container.x,
+            });
+        }
+
+        let mut mix_parts = Vec::new();
+        for (i, (name, pct)) in self.criteria.target_mix.iter().enumerate() {
+            mix_parts.push((lookup(format!("criteria.target_mix[{}]", i), name)?, *pct));
+        }
+
+        let layout = TableLayout {
+            containers,
+            ball_spawn_secs: self.ball_spawn_secs,
+            balance_position: Vec2::new(self.balance.0, self.balance.1),
+            table_position: Vec2::new(self.table.0, self.table.1),
+        };
+        let criteria = LevelCriteria {
+            ingredients: layout.ingredients(),
+            min_weight: self.criteria.min_weight,
+            target_mix: Mix::new(mix_parts),
+            mix_measure: self.criteria.mix_measure,
+            countdown_time_secs: self.criteria.countdown_secs,
+        };
+        Ok((criteria, layout))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainerLayout {
+    pub kind: BallKind,
+    pub container: ContainerKind,
+    pub x: f32,
+}
+
+/// Where everything on the table goes, and how often balls drop into the containers
+#[derive(Debug, Clone)]
+pub struct TableLayout {
+    pub containers: Vec<ContainerLayout>,
+    pub ball_spawn_secs: f32,
+    pub balance_position: Vec2,
+    pub table_position: Vec2,
+}
+
+impl TableLayout {
+    pub fn new_random<R: Rng>(ingredients: &[BallKind], rng: &mut R) -> Self {
+        let spawn_jar_instead = rng.gen_bool(0.25);
+        let count = ingredients.len().min(MAX_CONTAINERS);
+        let containers = ingredients
+            .iter()
+            .take(count)
+            .enumerate()
+            .map(|(slot, kind)| ContainerLayout {
+                kind: kind.clone(),
+                container: if slot == 0 && spawn_jar_instead {
+                    ContainerKind::Jar
+                } else {
+                    ContainerKind::Cup
+                },
+                x: Self::container_x_offset(slot, count),
+            })
+            .collect();
+        Self {
+            containers,
+            ball_spawn_secs: DEFAULT_BALL_SPAWN_SECS,
+            balance_position: Vec2::new(DEFAULT_BALANCE_POSITION.0, DEFAULT_BALANCE_POSITION.1),
+            table_position: Vec2::new(DEFAULT_TABLE_POSITION.0, DEFAULT_TABLE_POSITION.1),
+        }
+    }
+
+    /// Spread containers evenly either side of the balance, two containers sit at -50/50
+    fn container_x_offset(slot: usize, count: usize) -> f32 {
+        let spacing = if count <= 2 { 100.0 } else { 60.0 };
+        (slot as f32 - ((count as f32 - 1.0) / 2.0)) * spacing
+    }
+
+    /// Distinct ingredients across all containers, left to right
+    pub fn ingredients(&self) -> Vec<BallKind> {
+        let mut ingredients: Vec<BallKind> = Vec::new();
+        for container in self.containers.iter() {
+            if !ingredients.contains(&container.kind) {
+                ingredients.push(container.kind.clone());
+            }
+        }
+        ingredients
+    }
+}
+
+/// The same seed always rolls the same level
+pub fn new_random_level(registry: &BallKindRegistry, seed: u64) -> (LevelCriteria, TableLayout) {
+    let mut rng = seed::level_rng(seed);
+    let criteria = LevelCriteria::new_random(registry, &mut rng);
+    let layout = TableLayout::new_random(&criteria.ingredients, &mut rng);
+    (criteria, layout)
+}
+
+/// Roll the next level from `level_rng` and make it the one being played
+pub fn insert_random_level(
+    commands: &mut Commands,
+    registry: &BallKindRegistry,
+    level_rng: &mut LevelRng,
+) {
+    insert_seeded_level(commands, registry, level_rng.next_seed());
+}
+
+/// Play the level rolled from `seed`, which is the seed shown in game
+pub fn insert_seeded_level(commands: &mut Commands, registry: &BallKindRegistry, seed: u64) {
+    insert_level(commands, new_random_level(registry, seed));
+    commands.insert_resource(CurrentLevel::Random(seed));
+}
+
+/// Restarting retries authored levels and gives random ones a fresh roll
+pub fn insert_restart_level(
+    commands: &mut Commands,
+    registry: &BallKindRegistry,
+    current_level: &CurrentLevel,
+    level_rng: &mut LevelRng,
+    versus: Option<&Versus>,
+) {
+    if versus.is_some() {
+        versus::insert_versus_level(commands, registry, level_rng);
+    } else if let CurrentLevel::Random(_) = current_level {
+        insert_random_level(commands, registry, level_rng);
+    }
+}
+
+/// The level being played, from the start. Random levels are rebuilt from their seed, others are
+/// still in place
+pub fn insert_same_level(
+    commands: &mut Commands,
+    registry: &BallKindRegistry,
+    current_level: &CurrentLevel,
+    versus: Option<&Versus>,
+) {
+    if let CurrentLevel::Random(seed) = *current_level {
+        if versus.is_some() {
+            // A versus level's seed always fits
+            versus::insert_seeded_versus_level(commands, registry, seed);
+        } else {
+            insert_seeded_level(commands, registry, seed);
+        }
+    }
+}
+
+pub fn insert_level(commands: &mut Commands, level: (LevelCriteria, TableLayout)) {
+    let (criteria, layout) = level;
+    commands.insert_resource(criteria);
+    commands.insert_resource(layout);
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let level = LevelDef::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+pub struct LevelPlugin;
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LevelDef>()
+            .init_asset_loader::<LevelLoader>();
+    }
+}
+
+#[test]
+fn test_shown_seed_rebuilds_the_level() {
+    use crate::game::ingredients::IngredientsAsset;
+    use bevy::ecs::system::CommandQueue;
+
+    let ingredients = IngredientsAsset::from_bytes(include_bytes!(
+        "../../assets/ingredients/default.ingredients.ron"
+    ))
+    .unwrap();
+    let registry = BallKindRegistry::from_asset(&ingredients);
+    let insert = |insert_with: &mut dyn FnMut(&mut Commands)| {
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        insert_with(&mut Commands::new(&mut queue, &world));
+        queue.apply(&mut world);
+        let seed = match world.resource::<CurrentLevel>() {
+            CurrentLevel::Random(seed) => *seed,
+            _ => panic!("Not a random level"),
+        };
+        let level = format!(
+            "{:?} {:?}",
+            world.resource::<LevelCriteria>(),
+            world.resource::<TableLayout>()
+        );
+        (seed, level)
+    };
+
+    let mut level_rng = LevelRng::from_seed(7);
+    let (shown, rolled) =
+        insert(&mut |commands| insert_random_level(commands, &registry, &mut level_rng));
+    let (typed, entered) = insert(&mut |commands| insert_seeded_level(commands, &registry, shown));
+    assert_eq!(shown, typed);
+    assert_eq!(rolled, entered);
+}
+
+#[test]
+fn test_invalid_levels_name_the_bad_field() {
+    let level = include_str!("../../assets/levels/fifty_fifty.level.ron");
+    assert!(LevelDef::from_bytes(level.as_bytes()).is_ok());
+
+    let invalid_field = |from: &str, to: &str| {
+        assert!(level.contains(from));
+        let error = LevelDef::from_bytes(level.replace(from, to).as_bytes()).unwrap_err();
+        error.downcast::<LevelError>().unwrap().field
+    };
+    assert_eq!(
+        "criteria.min_weight",
+        invalid_field("min_weight: 3.0", "min_weight: -1.0")
+    );
+    assert_eq!(
+        "criteria.target_mix[1]",
+        invalid_field(r#"("Red", 50)]"#, r#"("Blue", 50)]"#)
+    );
+    assert_eq!(
+        "criteria.target_mix[1]",
+        invalid_field(r#"("Red", 50)]"#, r#"("Green", 50)]"#)
+    );
+    assert_eq!(
+        "criteria.target_mix[0]",
+        invalid_field(r#"("Blue", 50)"#, r#"("Blue", 0)"#)
+    );
+    assert_eq!(
+        "criteria.target_mix",
+        invalid_field(r#"("Red", 50)"#, r#"("Red", 40)"#)
+    );
+    assert_eq!(
+        "ball_spawn_secs",
+        invalid_field(
+            r#""Fifty Fifty","#,
+            r#""Fifty Fifty", ball_spawn_secs: 0.0,"#
+        )
+    );
+}
@@ -1,23 +1,46 @@
-mod audio;
-mod balance;
+pub mod audio;
+pub mod balance;
 mod ball;
+pub mod bot;
+pub mod campaign;
 mod components;
+pub mod control;
+pub mod coop;
 mod cup;
-mod goals;
+pub mod goals;
+pub mod ingredients;
+pub mod level;
 pub mod not_a_cup;
 mod overlay;
+mod pointer;
+pub mod replay;
+pub mod seed;
+pub mod table;
+pub mod timestep;
 mod ui;
+pub mod versus;
+mod weighing;
 
 pub use ball::BallKind;
 
 use crate::game::audio::AudioTriggerEvent;
+use crate::game::bot::BotPlugin;
+use crate::game::campaign::CampaignPlugin;
 use crate::game::components::GeneralComponentsPlugin;
-use crate::game::goals::{Countdown, LevelCriteria, LevelStopwatch};
+use crate::game::control::ControlInput;
+use crate::game::coop::CoopPlugin;
+use crate::game::goals::{LevelCompleteEvent, LevelStopwatch};
+use crate::game::ingredients::IngredientsPlugin;
+use crate::game::level::{LevelPlugin, TableLayout};
+use crate::game::replay::ReplayPlugin;
+use crate::game::seed::LevelRng;
+use crate::game::table::Table;
+use crate::game::versus::{Versus, VersusPlugin};
+use crate::manifest::Sprites;
 use crate::GameState;
-use balance::BalanceCounter;
 use bevy::prelude::*;
 
-use crate::game::ball::SpawnBallEvent;
+use crate::game::ball::{BallsSpawned, SpawnBallEvent};
 use bevy_rapier2d::geometry::Collider;
 use bevy_rapier2d::prelude::RigidBody;
 
@@ -26,43 +49,94 @@ pub struct GamePlugin;
 #[derive(Component)]
 struct GameOnlyMarker;
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+enum TickLabel {
+    Controls,
+    Stopwatch,
+    Results,
+}
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(BalanceCounter::new())
-            .insert_resource(LevelCriteria::new_random())
-            .insert_resource(goals::LevelStopwatch::new())
-            .insert_resource(Countdown::Inactive)
+        app.insert_resource(goals::LevelStopwatch::new())
+            .insert_resource(LevelRng::from_entropy())
+            .insert_resource(ControlInput::default())
             .add_event::<AudioTriggerEvent>()
             .add_event::<SpawnBallEvent>()
+            .init_resource::<BallsSpawned>()
+            .add_event::<LevelCompleteEvent>()
             .add_plugin(GeneralComponentsPlugin)
+            .add_plugin(IngredientsPlugin)
+            .add_plugin(LevelPlugin)
+            .add_plugin(CampaignPlugin)
+            .add_plugin(ReplayPlugin)
+            .add_plugin(BotPlugin)
+            .add_plugin(VersusPlugin)
+            .add_plugin(CoopPlugin)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Menu).with_system(goals::init_level_criteria),
+            )
             .add_system_set(
                 SystemSet::on_enter(GameState::Game)
                     .with_system(cup::spawn_cups)
                     .with_system(balance::spawn_balance)
                     .with_system(ui::setup_ui)
                     .with_system(reset_game_resources)
-                    .with_system(audio::setup_audio)
                     .with_system(spawn_background)
                     .with_system(goals::initial_goal_display),
             )
             .add_system_set(
                 SystemSet::on_update(GameState::Game)
-                    .with_system(ball::spawn_ball_system)
-                    .with_system(cup::rotate_cup_system)
                     .with_system(cup::ui_helper_show_system)
-                    .with_system(balance::ball_sensor_system)
                     .with_system(ui::TimerDisplay::update_system)
                     .with_system(ui::button_click_system)
-                    .with_system(goals::LevelStopwatch::update_system)
-                    .with_system(goals::LevelCriteria::watch_system)
                     .with_system(goals::debug_countdown_trigger_system)
-                    .with_system(goals::debug_overlay_system)
-                    .with_system(audio::triggered_audio_system)
-                    .with_system(overlay::timer_resume_watcher)
-                    .with_system(overlay::overlay_dismiss_system)
-                    .with_system(not_a_cup::JarDoor::system),
+                    .with_system(goals::debug_overlay_system),
             )
             .add_system_set(SystemSet::on_exit(GameState::Game).with_system(cleanup));
+
+        timestep::add_tick_systems(
+            app,
+            SystemSet::on_update(GameState::Game)
+                .with_system(goals::LevelStopwatch::update_system.label(TickLabel::Stopwatch))
+                // Spawn in the same tick the stopwatch asks for it
+                .with_system(ball::spawn_ball_system.after(TickLabel::Stopwatch))
+                .with_system(cup::rotate_cup_system.after(TickLabel::Controls))
+                .with_system(not_a_cup::JarDoor::system.after(TickLabel::Controls))
+                .with_system(overlay::overlay_dismiss_system.after(TickLabel::Controls))
+                .with_system(weighing::weighing_system)
+                .with_system(goals::LevelCriteria::watch_system.label(TickLabel::Results))
+                .with_system(campaign::record_result_system.after(TickLabel::Results))
+                .with_system(overlay::timer_resume_watcher),
+        );
+    }
+}
+
+/// Sound effects for the game, left out when running headless. The sounds themselves are
+/// loaded with everything else, see [crate::loading]
+pub struct GameAudioPlugin;
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Game).with_system(audio::triggered_audio_system),
+        );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+struct KeyboardControlLabel;
+
+/// Drives [ControlInput] from the keyboard, gamepads, mouse and touch, left out when something
+/// else is in control
+pub struct PlayerControlPlugin;
+impl Plugin for PlayerControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<pointer::PointerDrags>().add_system_set(
+            SystemSet::on_update(GameState::Game)
+                .with_system(control::keyboard_control_system.label(KeyboardControlLabel))
+                .with_system(control::gamepad_control_system.after(KeyboardControlLabel))
+                .with_system(pointer::pointer_control_system.after(KeyboardControlLabel)),
+        );
     }
 }
 
@@ -79,19 +153,25 @@ app.insert_resource(BalanceCounter::new())
 .insert_resource(Countdown::Inactive)
  */
 
+// Each table's count and countdown start afresh with its balance, see balance::spawn_balance
 fn reset_game_resources(
     mut stopwatch: ResMut<LevelStopwatch>,
-    mut countdown: ResMut<Countdown>,
-    mut balance_counter: ResMut<BalanceCounter>,
+    mut balls_spawned: ResMut<BallsSpawned>,
+    layout: Res<TableLayout>,
 ) {
+    stopwatch.set_spawn_interval(layout.ball_spawn_secs);
     stopwatch.reset();
-    countdown.reset();
-    balance_counter.reset();
+    *balls_spawned = BallsSpawned::default();
 }
 
-fn spawn_background(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let background_image = asset_server.load("background.png");
-    let table_image = asset_server.load("table.png");
+fn spawn_background(
+    mut commands: Commands,
+    sprites: Res<Sprites>,
+    layout: Res<TableLayout>,
+    versus: Option<Res<Versus>>,
+) {
+    let background_image = sprites.background.clone();
+    let table_image = sprites.table.clone();
 
     commands
         .spawn()
@@ -107,32 +187,38 @@ fn spawn_background(mut commands: Commands, asset_server: Res<AssetServer>) {
         .insert(GameOnlyMarker)
         .insert(Background);
 
-    let (table_transform, sub_transform) = {
-        let x = 0.0;
-        let y = -160.0;
-        let z = 1.0;
-        let sub_offset = 54.0;
-        (
-            Transform::from_xyz(x, y, z),
-            Transform::from_xyz(0.0, sub_offset, 0.0),
-        )
-    };
-    commands
-        .spawn()
-        .insert_bundle(SpriteBundle {
-            transform: table_transform,
-            texture: table_image,
-            ..default()
-        })
-        .insert(RigidBody::Fixed)
-        .with_children(|parent| {
-            parent
-                .spawn()
-                .insert(sub_transform)
-                .insert(Collider::cuboid(198.0 / 2.0, 9.0 / 2.0));
-        })
-        .insert(GameOnlyMarker)
-        .insert(Background);
+    for (table, offset) in Table::offsets(versus::table_count(versus.as_deref()))
+        .into_iter()
+        .enumerate()
+    {
+        let (table_transform, sub_transform) = {
+            let x = layout.table_position.x + offset;
+            let y = layout.table_position.y;
+            let z = 1.0;
+            let sub_offset = 54.0;
+            (
+                Transform::from_xyz(x, y, z),
+                Transform::from_xyz(0.0, sub_offset, 0.0),
+            )
+        };
+        commands
+            .spawn()
+            .insert_bundle(SpriteBundle {
+                transform: table_transform,
+                texture: table_image.clone(),
+                ..default()
+            })
+            .insert(RigidBody::Fixed)
+            .with_children(|parent| {
+                parent
+                    .spawn()
+                    .insert(sub_transform)
+                    .insert(Collider::cuboid(198.0 / 2.0, 9.0 / 2.0));
+            })
+            .insert(GameOnlyMarker)
+            .insert(Table(table))
+            .insert(Background);
+    }
 }
 
 #[derive(Component)]
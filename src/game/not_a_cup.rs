@@ -1,20 +1,17 @@
 use crate::game::ball::BallKind;
+use crate::game::control::{ControlInput, DoorMove};
 use crate::game::cup::{Cup, CupUiHelper};
 use crate::game::GameOnlyMarker;
+use crate::manifest::Sprites;
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use bevy_rapier2d::prelude::*;
 use std::time::Duration;
 
-pub fn spawn_jar(
-    x_offset: f32,
-    ball_kind: BallKind,
-    commands: &mut Commands,
-    asset_server: &AssetServer,
-) {
-    let sprite_tex = asset_server.load("tank.png");
-    let door_tex = asset_server.load("tank_door.png");
-    let ui_helper_tex = asset_server.load("ui_helper_jar_l.png");
+pub fn spawn_jar(x_offset: f32, ball_kind: BallKind, commands: &mut Commands, sprites: &Sprites) {
+    let sprite_tex = sprites.tank.clone();
+    let door_tex = sprites.tank_door.clone();
+    let ui_helper_tex = sprites.helper_jar.clone();
 
     let shape = {
         let thickness = 4.0;
@@ -136,23 +133,13 @@ impl JarDoor {
     pub fn system(
         mut commands: Commands,
         mut door_query: Query<(&mut Transform, &mut Velocity, &mut JarDoor)>,
-        input: Res<Input<KeyCode>>,
+        control: Res<ControlInput>,
     ) {
-        let vy = if input.pressed(KeyCode::W) {
-            2.0
-        } else if input.pressed(KeyCode::S) {
-            -2.0
-        } else {
-            0.0
-        };
-
         for (mut transform, mut velocity, mut door) in door_query.iter_mut() {
-            let vy = if input.pressed(KeyCode::W) {
-                20.0
-            } else if input.pressed(KeyCode::S) {
-                -20.0
-            } else {
-                0.0
+            let vy = match control.door {
+                DoorMove::Up => 20.0,
+                DoorMove::Down => -20.0,
+                DoorMove::Still => 0.0,
             };
             velocity.linvel.y = vy;
 
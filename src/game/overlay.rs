@@ -1,4 +1,5 @@
 use crate::game::components::TimedRemoval;
+use crate::game::control::ControlInput;
 use crate::game::cup::CupUiHelper;
 use crate::game::goals::LevelStopwatch;
 use crate::game::GameOnlyMarker;
@@ -12,7 +13,7 @@ pub fn spawn(
     commands: &mut Commands,
     text_style: TextStyle,
     text_lines: Vec<impl Into<String>>,
-    mut level_stopwatch: ResMut<LevelStopwatch>,
+    level_stopwatch: &mut LevelStopwatch,
 ) {
     commands
         .spawn_bundle(nodes::new(nodes::defaults::full(
@@ -54,11 +55,12 @@ pub fn timer_resume_watcher(
 pub fn overlay_dismiss_system(
     mut commands: Commands,
     query: Query<Entity, With<Overlay>>,
-    input: Res<Input<KeyCode>>,
+    mut control: ResMut<ControlInput>,
 ) {
-    if !query.is_empty() && input.just_pressed(KeyCode::Return) {
+    if !query.is_empty() && control.dismiss {
         despawn(&mut commands, &query);
     }
+    control.dismiss = false;
 }
 
 fn centred_div(fd: FlexDirection) -> Vec<Property> {
@@ -0,0 +1,181 @@
+use crate::game::control::{ControlInput, DoorMove, Tilt};
+use crate::game::cup::Cup;
+use crate::game::not_a_cup::JarDoor;
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+// How far a drag goes sideways from where it started to tilt a cup at full speed, in world units
+const FULL_TILT_DRAG: f32 = 30.0;
+// Drags shorter than this leave things still, so a resting finger doesn't wobble them
+const DRAG_DEAD_ZONE: f32 = 3.0;
+// A press this close to a cup's middle grabs it
+const CUP_GRAB_RADIUS: f32 = 40.0;
+const DOOR_GRAB_RADIUS: f32 = 15.0;
+// A press let go without moving further than this is a tap
+const TAP_DISTANCE: f32 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PointerId {
+    Mouse,
+    Touch(u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Grab {
+    Cup(usize),
+    Door,
+    Nothing,
+}
+
+struct Drag {
+    grab: Grab,
+    start: Vec2,
+    moved: bool,
+}
+
+/// What each mouse button or finger held down is dragging
+#[derive(Default)]
+pub struct PointerDrags(HashMap<PointerId, Drag>);
+
+struct Pointer {
+    id: PointerId,
+    /// Window coordinates, from the bottom left
+    position: Vec2,
+    released: bool,
+}
+
+fn pointers(window: &Window, mouse: &Input<MouseButton>, touches: &Touches) -> Vec<Pointer> {
+    let mut pointers = Vec::new();
+    if let Some(position) = window.cursor_position() {
+        if mouse.pressed(MouseButton::Left) || mouse.just_released(MouseButton::Left) {
+            pointers.push(Pointer {
+                id: PointerId::Mouse,
+                position,
+                released: !mouse.pressed(MouseButton::Left),
+            });
+        }
+    }
+    // Touches are measured from the top left, unlike the cursor
+    let flip = |position: Vec2| Vec2::new(position.x, window.height() - position.y);
+    for touch in touches.iter() {
+        pointers.push(Pointer {
+            id: PointerId::Touch(touch.id()),
+            position: flip(touch.position()),
+            released: false,
+        });
+    }
+    for touch in touches.iter_just_released() {
+        pointers.push(Pointer {
+            id: PointerId::Touch(touch.id()),
+            position: flip(touch.position()),
+            released: true,
+        });
+    }
+    pointers
+}
+
+fn window_to_world(
+    position: Vec2,
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Vec2 {
+    let window_size = Vec2::new(window.width(), window.height());
+    let ndc = (position / window_size) * 2.0 - Vec2::ONE;
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+    ndc_to_world.project_point3(ndc.extend(-1.0)).truncate()
+}
+
+fn drag_amount(offset: f32) -> f32 {
+    if offset.abs() < DRAG_DEAD_ZONE {
+        0.0
+    } else {
+        // Rounded like a stick, so replays aren't filled with tiny changes
+        ((offset / FULL_TILT_DRAG).clamp(-1.0, 1.0) * 100.0).round() / 100.0
+    }
+}
+
+/// Dragging sideways on a cup tilts it like a stick pushed that far, dragging the jar door
+/// moves it towards the pointer, and tapping anywhere else dismisses the overlay. Each finger
+/// drags on its own, so two cups can be tilted at once
+pub fn pointer_control_system(
+    windows: Res<Windows>,
+    mouse: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    cups: Query<(&Transform, &Cup)>,
+    doors: Query<&Transform, With<JarDoor>>,
+    mut drags: ResMut<PointerDrags>,
+    mut control: ResMut<ControlInput>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let (camera, camera_transform) = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+
+    let pointers = pointers(window, &mouse, &touches);
+    // Anything no longer held, like a mouse let go outside the window, stops dragging
+    drags
+        .0
+        .retain(|id, _| pointers.iter().any(|pointer| pointer.id == *id));
+
+    for pointer in pointers {
+        let position = window_to_world(pointer.position, window, camera, camera_transform);
+        let drag = drags.0.entry(pointer.id).or_insert_with(|| {
+            let grab = if doors
+                .iter()
+                .any(|door| door.translation.truncate().distance(position) < DOOR_GRAB_RADIUS)
+            {
+                Grab::Door
+            } else {
+                // Cups sit on their bottom edge, so their middle is a little above
+                cups.iter()
+                    .find(|(transform, _)| {
+                        let middle = transform.translation.truncate() + Vec2::new(0.0, 15.0);
+                        middle.distance(position) < CUP_GRAB_RADIUS
+                    })
+                    .map_or(Grab::Nothing, |(_, cup)| Grab::Cup(cup.slot))
+            };
+            Drag {
+                grab,
+                start: position,
+                moved: false,
+            }
+        });
+        drag.moved |= drag.start.distance(position) > TAP_DISTANCE;
+
+        match drag.grab {
+            Grab::Cup(slot) if !pointer.released => {
+                if let Some(tilt) = control.tilts.get_mut(slot) {
+                    // Dragging right tips the cup right, which is clockwise
+                    let amount = drag_amount(position.x - drag.start.x);
+                    *tilt = if amount == 0.0 {
+                        Tilt::Still
+                    } else {
+                        Tilt::Analog(-amount)
+                    };
+                }
+            }
+            Grab::Door if !pointer.released => {
+                if let Some(door) = doors.iter().next() {
+                    control.door = match drag_amount(position.y - door.translation.y) {
+                        amount if amount > 0.0 => DoorMove::Up,
+                        amount if amount < 0.0 => DoorMove::Down,
+                        _ => DoorMove::Still,
+                    };
+                }
+            }
+            Grab::Nothing if pointer.released && !drag.moved => control.dismiss = true,
+            _ => (),
+        }
+
+        if pointer.released {
+            drags.0.remove(&pointer.id);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::game::bot::Demo;
+use crate::game::campaign::{Campaign, CurrentLevel};
+use crate::game::control::ControlInput;
+use crate::game::goals::LevelCompleteEvent;
+use crate::game::timestep;
+use crate::game::versus::Versus;
+use crate::game::TickLabel;
+use crate::loading::LevelArg;
+use crate::save::Save;
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const REPLAY_VERSION: u32 = 1;
+/// Every finished level is kept here, next to the save
+pub const LAST_REPLAY_KEY: &str = "last.replay.ron";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReplayLevel {
+    /// A random level rolled from this seed
+    Seed(u64),
+    /// A level file, relative to the assets folder
+    File(String),
+}
+
+/// The level played and every input given, enough to play the level again exactly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    version: u32,
+    pub level: ReplayLevel,
+    /// Only ticks where the input changed are kept, as (level tick, input)
+    inputs: Vec<(u64, ControlInput)>,
+    pub score: Option<f32>,
+}
+
+impl Replay {
+    fn new(level: ReplayLevel) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            level,
+            inputs: Vec::new(),
+            score: None,
+        }
+    }
+
+    pub fn from_ron(contents: &str) -> Result<Self, String> {
+        let replay: Self = ron::from_str(contents).map_err(|e| e.to_string())?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "replay is version {}, only version {} can be played",
+                replay.version, REPLAY_VERSION
+            ));
+        }
+        Ok(replay)
+    }
+
+    pub fn inputs(&self) -> &[(u64, ControlInput)] {
+        &self.inputs
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Replays the level the player is on, if it can be played again
+    fn of_current_level(
+        current_level: &CurrentLevel,
+        campaign: &Campaign,
+        level_arg: &LevelArg,
+    ) -> Option<Self> {
+        let level = match current_level {
+            CurrentLevel::Random(seed) => ReplayLevel::Seed(*seed),
+            CurrentLevel::Authored => ReplayLevel::File(level_arg.0.clone()?),
+            CurrentLevel::Campaign(index) => ReplayLevel::File(campaign.get(*index)?.path.clone()),
+        };
+        Some(Self::new(level))
+    }
+}
+
+/// A replay passed as `--replay <file>`, played instead of going to the menu
+pub struct ReplayPlayback(pub Replay);
+
+impl ReplayPlayback {
+    fn from_args() -> Option<Self> {
+        let mut args = std::env::args().skip_while(|arg| arg != "--replay");
+        args.next();
+        let path = args.next()?;
+        let replay = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| Replay::from_ron(&contents));
+        match replay {
+            Ok(replay) => Some(Self(replay)),
+            Err(e) => {
+                error!("Can't play replay {}: {}", path, e);
+                None
+            }
+        }
+    }
+}
+
+enum ReplayState {
+    Idle,
+    Recording {
+        replay: Replay,
+        last: Option<ControlInput>,
+    },
+    Playing {
+        replay: Replay,
+        next: usize,
+        current: ControlInput,
+    },
+}
+
+/// Ticks since the level started
+struct LevelTick(u64);
+
+fn start_system(
+    mut replay_state: ResMut<ReplayState>,
+    mut level_tick: ResMut<LevelTick>,
+    mut control: ResMut<ControlInput>,
+    playback: Option<Res<ReplayPlayback>>,
+    current_level: Res<CurrentLevel>,
+    campaign: Res<Campaign>,
+    level_arg: Res<LevelArg>,
+    versus: Option<Res<Versus>>,
+    demo: Option<Res<Demo>>,
+) {
+    level_tick.0 = 0;
+    *control = ControlInput::default();
+    *replay_state = match playback {
+        Some(playback) => ReplayState::Playing {
+            replay: playback.0.clone(),
+            next: 0,
+            current: ControlInput::default(),
+        },
+        // Versus changes the level's layout and has a table per player, which a replay can't
+        // play back. The menu's demo shouldn't replace the player's last replay
+        None if versus.is_some() || demo.is_some() => ReplayState::Idle,
+        None => match Replay::of_current_level(&current_level, &campaign, &level_arg) {
+            Some(replay) => ReplayState::Recording { replay, last: None },
+            None => ReplayState::Idle,
+        },
+    };
+}
+
+/// Runs at the start of every tick, before anything reads [ControlInput]
+fn tick_system(
+    mut replay_state: ResMut<ReplayState>,
+    mut level_tick: ResMut<LevelTick>,
+    mut control: ResMut<ControlInput>,
+) {
+    let tick = level_tick.0;
+    match &mut *replay_state {
+        ReplayState::Idle => (),
+        ReplayState::Recording { replay, last } => {
+            if *last != Some(*control) {
+                replay.inputs.push((tick, *control));
+                *last = Some(*control);
+            }
+        }
+        ReplayState::Playing {
+            replay,
+            next,
+            current,
+        } => {
+            while let Some((input_tick, input)) = replay.inputs.get(*next) {
+                if *input_tick > tick {
+                    break;
+                }
+                *current = *input;
+                *next += 1;
+            }
+            *control = *current;
+        }
+    }
+    level_tick.0 += 1;
+}
+
+fn finish_system(
+    mut event_reader: EventReader<LevelCompleteEvent>,
+    mut replay_state: ResMut<ReplayState>,
+    save: Res<Save>,
+) {
+    for event in event_reader.iter() {
+        match std::mem::replace(&mut *replay_state, ReplayState::Idle) {
+            ReplayState::Idle => (),
+            ReplayState::Recording { mut replay, .. } => {
+                replay.score = Some(event.score);
+                let result = replay
+                    .to_ron()
+                    .and_then(|contents| save.write_extra(LAST_REPLAY_KEY, &contents));
+                match result {
+                    Ok(()) => info!("Saved replay as {}", LAST_REPLAY_KEY),
+                    Err(e) => error!("Failed to save replay: {}", e),
+                }
+            }
+            ReplayState::Playing { replay, .. } => match replay.score {
+                Some(recorded) if recorded == event.score => {
+                    info!("Replay finished with the recorded score {:.2}", recorded)
+                }
+                Some(recorded) => warn!(
+                    "Replay finished with score {:.2}, but {:.2} was recorded",
+                    event.score, recorded
+                ),
+                None => info!("Replay finished with score {:.2}", event.score),
+            },
+        }
+    }
+}
+
+/// Playback is over once we're back on the menu
+fn stop_playback_system(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(playback) = ReplayPlayback::from_args() {
+            app.insert_resource(playback);
+        }
+        app.insert_resource(ReplayState::Idle)
+            .insert_resource(LevelTick(0))
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(start_system))
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(stop_playback_system));
+        timestep::add_tick_systems(
+            app,
+            SystemSet::on_update(GameState::Game)
+                .with_system(tick_system.label(TickLabel::Controls))
+                .with_system(finish_system.after(TickLabel::Results)),
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Every random level is rolled from its own seed, the seeds come from here. Seeding this the
+/// same way gives the same run of levels
+pub struct LevelRng {
+    rng: StdRng,
+    /// Set when seeded from the date, as `YYYY-MM-DD`
+    daily: Option<String>,
+}
+
+impl LevelRng {
+    pub fn from_entropy() -> Self {
+        Self {
+            rng: StdRng::from_entropy(),
+            daily: None,
+        }
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            daily: None,
+        }
+    }
+
+    /// Everyone playing on the same (UTC) day gets the same levels
+    pub fn daily() -> Self {
+        let days = days_since_epoch();
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            rng: StdRng::seed_from_u64(days as u64),
+            daily: Some(format!("{:04}-{:02}-{:02}", year, month, day)),
+        }
+    }
+
+    pub fn next_seed(&mut self) -> u64 {
+        self.rng.gen()
+    }
+
+    pub fn daily_date(&self) -> Option<&str> {
+        self.daily.as_deref()
+    }
+
+    /// How a level's seed is shown to the player
+    pub fn seed_text(&self, seed: u64) -> String {
+        match self.daily_date() {
+            Some(date) => format!("Daily {} - Seed {}", date, seed),
+            None => format!("Seed {}", seed),
+        }
+    }
+}
+
+pub fn level_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn days_since_epoch() -> i64 {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    (secs / 86_400) as i64
+}
+
+// SystemTime isn't available in the browser
+#[cfg(target_arch = "wasm32")]
+fn days_since_epoch() -> i64 {
+    (js_sys::Date::now() / 86_400_000.0).floor() as i64
+}
+
+/// Days since 1970-01-01 to (year, month, day), from Howard Hinnant's date algorithms
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[test]
+fn test_civil_from_days() {
+    assert_eq!((1970, 1, 1), civil_from_days(0));
+    assert_eq!((2000, 2, 29), civil_from_days(11_016));
+    assert_eq!((2022, 8, 1), civil_from_days(19_205));
+}
@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+
+// Distance between the middles of neighbouring tables, when there's more than one
+const TABLE_SPACING: f32 = 260.0;
+
+/// Which table something belongs to, counted from the left. There's only table 0 unless it's
+/// [Versus](crate::game::versus::Versus)
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Table(pub usize);
+
+impl Table {
+    /// How far each table is shifted sideways from the positions in the level's layout
+    pub fn offsets(count: usize) -> Vec<f32> {
+        (0..count)
+            .map(|index| (index as f32 - (count as f32 - 1.0) / 2.0) * TABLE_SPACING)
+            .collect()
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::time::Duration;
+
+// The simulation (level timers, containers, ball spawns and physics) only ever moves forward in
+// steps of this size, so the same inputs give the same result however fast the frames come
+pub const TICK_SECS: f32 = 1.0 / 60.0;
+pub const TICK: Duration = Duration::from_nanos(16_666_667);
+// When frames are slow we'd rather the game slow down than spiral trying to catch up
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct FixedUpdateStage;
+
+/// Game systems run here each tick, before physics is stepped
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct GameTickStage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockMode {
+    /// Ticks as real time passes
+    RealTime,
+    /// Only ticks when told to with [`SimClock::advance`], for running without a window
+    Manual,
+}
+
+pub struct SimClock {
+    mode: ClockMode,
+    accumulator: Duration,
+    pending_ticks: u32,
+    ticks: u64,
+    looping: bool,
+}
+
+impl SimClock {
+    pub fn new(mode: ClockMode) -> Self {
+        Self {
+            mode,
+            accumulator: Duration::ZERO,
+            pending_ticks: 0,
+            ticks: 0,
+            looping: false,
+        }
+    }
+
+    /// Queue up ticks to run next frame, only used in [`ClockMode::Manual`]
+    pub fn advance(&mut self, ticks: u32) {
+        self.pending_ticks += ticks;
+    }
+
+    /// Ticks run since startup
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    fn run_criteria(time: Res<Time>, mut clock: ResMut<Self>) -> ShouldRun {
+        if !clock.looping && clock.mode == ClockMode::RealTime {
+            clock.accumulator += time.delta();
+            let max_behind = TICK * MAX_TICKS_PER_FRAME;
+            if clock.accumulator > max_behind {
+                clock.accumulator = max_behind;
+            }
+            clock.pending_ticks = (clock.accumulator.as_nanos() / TICK.as_nanos()) as u32;
+            clock.accumulator -= TICK * clock.pending_ticks;
+        }
+
+        if clock.pending_ticks > 0 {
+            clock.pending_ticks -= 1;
+            clock.ticks += 1;
+            clock.looping = true;
+            ShouldRun::YesAndCheckAgain
+        } else {
+            clock.looping = false;
+            ShouldRun::No
+        }
+    }
+}
+
+pub fn add_tick_systems(app: &mut App, system_set: SystemSet) -> &mut App {
+    app.stage(FixedUpdateStage, |schedule: &mut Schedule| {
+        schedule.add_system_set_to_stage(GameTickStage, system_set)
+    })
+}
+
+fn physics_stage(stage: PhysicsStages) -> SystemStage {
+    SystemStage::parallel().with_system_set(RapierPhysicsPlugin::<NoUserData>::get_systems(stage))
+}
+
+fn configure_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: TICK_SECS,
+        substeps: 1,
+    };
+}
+
+/// Runs the simulation on a fixed tick, taking over rapier's stages (the rapier plugin must be
+/// added with its default system setup turned off)
+pub struct TimestepPlugin {
+    pub mode: ClockMode,
+}
+
+impl Plugin for TimestepPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = Schedule::default()
+            .with_run_criteria(SimClock::run_criteria)
+            .with_stage(GameTickStage, SystemStage::parallel())
+            .with_stage_after(
+                GameTickStage,
+                PhysicsStages::SyncBackend,
+                physics_stage(PhysicsStages::SyncBackend),
+            )
+            .with_stage_after(
+                PhysicsStages::SyncBackend,
+                PhysicsStages::StepSimulation,
+                physics_stage(PhysicsStages::StepSimulation),
+            )
+            .with_stage_after(
+                PhysicsStages::StepSimulation,
+                PhysicsStages::Writeback,
+                physics_stage(PhysicsStages::Writeback),
+            );
+
+        app.insert_resource(SimClock::new(self.mode))
+            .add_stage_after(CoreStage::Update, FixedUpdateStage, schedule)
+            .add_stage_before(
+                CoreStage::Last,
+                PhysicsStages::DetectDespawn,
+                physics_stage(PhysicsStages::DetectDespawn),
+            )
+            .add_startup_system(configure_physics);
+    }
+}
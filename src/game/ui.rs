@@ -3,20 +3,34 @@ use bevy::prelude::*;
 use nodes::Property;
 
 use crate::game::audio::AudioTriggerEvent;
+use crate::game::campaign::CurrentLevel;
+use crate::game::control::KeyBindings;
+use crate::game::coop::{self, Coop};
 use crate::game::goals::{Countdown, LevelCriteria, LevelStopwatch};
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level::{self, TableLayout};
+use crate::game::seed::LevelRng;
+use crate::game::versus::Versus;
 use crate::game::GameOnlyMarker;
+use crate::manifest::{ButtonImages, Fonts};
 use crate::ui_core::buttons::ButtonComponent;
 use crate::ui_core::nodes;
 use crate::{ui_core, GameState, TotalScore};
 
 pub fn setup_ui(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    fonts: Res<Fonts>,
+    button_images: Res<ButtonImages>,
     criteria: Res<LevelCriteria>,
     total_score: Res<TotalScore>,
+    current_level: Res<CurrentLevel>,
+    level_rng: Res<LevelRng>,
+    layout: Res<TableLayout>,
+    bindings: Res<KeyBindings>,
+    coop: Option<Res<Coop>>,
 ) {
     let text_style = TextStyle {
-        font: asset_server.load("Quicksand-Regular.ttf"),
+        font: fonts.regular.clone(),
         font_size: 20.0,
         color: Default::default(),
     };
@@ -26,8 +40,8 @@ pub fn setup_ui(
         color: Default::default(),
     };
 
-    let exit_button = asset_server.load("buttons/exit.png");
-    let reset_button = asset_server.load("buttons/restart.png");
+    let exit_button = button_images.exit.clone();
+    let reset_button = button_images.restart.clone();
 
     commands
         .spawn_bundle(nodes::new(nodes::defaults::full(
@@ -59,7 +73,7 @@ pub fn setup_ui(
                     parent
                         .spawn_bundle(nodes::new(full_height_half_width()))
                         .with_children(|parent| {
-                            TimerDisplay::create(parent, &asset_server);
+                            TimerDisplay::create(parent, &fonts);
                         });
                 });
             parent
@@ -93,6 +107,12 @@ pub fn setup_ui(
                                 None,
                                 None,
                             );
+                            crate::ui_core::buttons::make_button_custom_size(
+                                GameActionButton::Pause,
+                                Size::new(Val::Px(110.0), Val::Px(40.0)),
+                                parent,
+                                text_style.font.clone(),
+                            );
 
                             parent.spawn_bundle(TextBundle {
                                 style: Style {
@@ -125,10 +145,49 @@ pub fn setup_ui(
                                 },
                                 text: Text::from_section(
                                     format!("Total score: {:.2}", total_score.total()),
-                                    score_text_style,
+                                    score_text_style.clone(),
                                 ),
                                 ..default()
                             });
+
+                            if let CurrentLevel::Random(seed) = *current_level {
+                                parent.spawn_bundle(TextBundle {
+                                    style: Style {
+                                        max_size: Size::new(Val::Px(100.0), Val::Auto),
+                                        margin: UiRect::new(
+                                            Val::Px(10.0),
+                                            Val::Auto,
+                                            Val::Auto,
+                                            Val::Px(15.0),
+                                        ),
+                                        ..default()
+                                    },
+                                    text: Text::from_section(
+                                        level_rng.seed_text(seed),
+                                        score_text_style.clone(),
+                                    ),
+                                    ..default()
+                                });
+                            }
+
+                            if coop.is_some() {
+                                for line in coop::player_key_texts(&criteria, &layout, &bindings) {
+                                    parent.spawn_bundle(TextBundle {
+                                        style: Style {
+                                            max_size: Size::new(Val::Px(100.0), Val::Auto),
+                                            margin: UiRect::new(
+                                                Val::Px(10.0),
+                                                Val::Auto,
+                                                Val::Auto,
+                                                Val::Px(15.0),
+                                            ),
+                                            ..default()
+                                        },
+                                        text: Text::from_section(line, score_text_style.clone()),
+                                        ..default()
+                                    });
+                                }
+                            }
                         });
                 });
         });
@@ -141,14 +200,25 @@ pub fn button_click_system(
     interaction_query: Query<InteractionAndButton, ButtonInteractionChanged>,
     mut state: ResMut<State<GameState>>,
     mut commands: Commands,
+    registry: Res<BallKindRegistry>,
+    current_level: Res<CurrentLevel>,
+    mut level_rng: ResMut<LevelRng>,
+    versus: Option<Res<Versus>>,
 ) {
     for (interaction, game_action_button) in &interaction_query {
         match *interaction {
             Interaction::Clicked => match *game_action_button {
                 GameActionButton::Reset => {
-                    commands.insert_resource(LevelCriteria::new_random());
+                    level::insert_restart_level(
+                        &mut commands,
+                        &registry,
+                        &current_level,
+                        &mut level_rng,
+                        versus.as_deref(),
+                    );
                     state.restart().unwrap();
                 }
+                GameActionButton::Pause => state.push(GameState::Paused).unwrap(),
                 GameActionButton::Exit => {
                     state.set(GameState::Menu).unwrap();
                 }
@@ -161,6 +231,7 @@ pub fn button_click_system(
 #[derive(Component)]
 pub enum GameActionButton {
     Reset,
+    Pause,
     Exit,
 }
 
@@ -168,6 +239,7 @@ impl ButtonComponent for GameActionButton {
     fn to_text(&self) -> &'static str {
         match self {
             GameActionButton::Reset => "Reset",
+            GameActionButton::Pause => "Pause",
             GameActionButton::Exit => "Exit",
         }
     }
@@ -181,14 +253,14 @@ pub struct TimerDisplay {
 }
 
 impl TimerDisplay {
-    fn create(parent: &mut ChildBuilder, asset_server: &AssetServer) {
+    fn create(parent: &mut ChildBuilder, fonts: &Fonts) {
         let text_style = TextStyle {
-            font: asset_server.load("Quicksand-Regular.ttf"),
+            font: fonts.regular.clone(),
             font_size: 30.0,
             color: Default::default(),
         };
         let countdown_style = TextStyle {
-            font: asset_server.load("Quicksand-Bold.ttf"),
+            font: fonts.bold.clone(),
             font_size: 30.0,
             color: Color::RED,
         };
@@ -231,21 +303,26 @@ impl TimerDisplay {
     pub fn update_system(
         mut self_query: Query<&mut Self>,
         mut text_query: Query<&mut Text, With<Self>>,
-        countdown: Res<Countdown>,
+        countdowns: Query<&Countdown>,
         level_stopwatch: Res<LevelStopwatch>,
         mut audio_trigger_event_writer: EventWriter<AudioTriggerEvent>,
     ) {
         for mut timer_display in self_query.iter_mut() {
             let round_seconds = level_stopwatch.stopwatch.elapsed_secs().floor();
             if round_seconds > timer_display.last_secs {
+                // With more than one table, the countdown closest to running out is shown
+                let countdown_end = countdowns
+                    .iter()
+                    .filter_map(|countdown| match countdown {
+                        Countdown::Active { end, .. } => Some(*end),
+                        Countdown::Inactive => None,
+                    })
+                    .reduce(f32::min);
                 //update text
                 let (text_style, mins, secs, is_countdown) = {
-                    let (text_style, secs_total, is_countdown) = match *countdown {
-                        Countdown::Inactive => (&timer_display.normal_style, round_seconds, false),
-                        Countdown::Active {
-                            end,
-                            end_calculated: _,
-                        } => {
+                    let (text_style, secs_total, is_countdown) = match countdown_end {
+                        None => (&timer_display.normal_style, round_seconds, false),
+                        Some(end) => {
                             let mut remaining_time = end - round_seconds;
                             if remaining_time < 0.0 {
                                 remaining_time = 0.0;
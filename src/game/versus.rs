@@ -0,0 +1,157 @@
+use crate::game::balance::BalanceCounter;
+use crate::game::campaign::CurrentLevel;
+use crate::game::control::MAX_CONTAINERS;
+use crate::game::goals::{LevelCriteria, LevelResults, LevelStopwatch, TableResults};
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level::{self, ContainerKind, TableLayout};
+use crate::game::seed::LevelRng;
+use crate::game::table::Table;
+use crate::game::{timestep, TickLabel};
+use crate::GameState;
+use bevy::prelude::*;
+
+pub const PLAYERS: usize = 2;
+
+/// While this resource exists the level is played by two players, a table each
+#[derive(Default)]
+pub struct Versus {
+    finished: bool,
+}
+
+pub fn table_count(versus: Option<&Versus>) -> usize {
+    if versus.is_some() {
+        PLAYERS
+    } else {
+        1
+    }
+}
+
+/// The level rolled from `seed`, if it fits a table each. Each player only has keys for half
+/// the containers, and there's only one set of jar door keys so any jar becomes a cup
+pub fn versus_level(
+    registry: &BallKindRegistry,
+    seed: u64,
+) -> Option<(LevelCriteria, TableLayout)> {
+    let (criteria, mut layout) = level::new_random_level(registry, seed);
+    if layout.containers.len() * PLAYERS > MAX_CONTAINERS {
+        return None;
+    }
+    for container in layout.containers.iter_mut() {
+        container.container = ContainerKind::Cup;
+    }
+    Some((criteria, layout))
+}
+
+/// Rolls levels from `level_rng` until one fits a table each
+pub fn insert_versus_level(
+    commands: &mut Commands,
+    registry: &BallKindRegistry,
+    level_rng: &mut LevelRng,
+) {
+    loop {
+        if insert_seeded_versus_level(commands, registry, level_rng.next_seed()) {
+            return;
+        }
+    }
+}
+
+/// Plays the level rolled from `seed` if it fits, so the seed shown in versus can be typed in
+/// again. Returns whether it did
+pub fn insert_seeded_versus_level(
+    commands: &mut Commands,
+    registry: &BallKindRegistry,
+    seed: u64,
+) -> bool {
+    match versus_level(registry, seed) {
+        Some(level) => {
+            level::insert_level(commands, level);
+            commands.insert_resource(CurrentLevel::Random(seed));
+            true
+        }
+        None => false,
+    }
+}
+
+fn winner_text(scores: &[(usize, f32)]) -> String {
+    let best = scores
+        .iter()
+        .map(|(_, score)| *score)
+        .fold(f32::MIN, f32::max);
+    let winners: Vec<usize> = scores
+        .iter()
+        .filter(|(_, score)| *score == best)
+        .map(|(table, _)| *table)
+        .collect();
+    match winners.as_slice() {
+        [winner] => format!("Player {} wins!", winner + 1),
+        _ => "It's a draw!".to_string(),
+    }
+}
+
+/// Moves on to the results once the last countdown is over, with every table's results and who
+/// won
+fn results_system(
+    mut commands: Commands,
+    versus: Option<ResMut<Versus>>,
+    tables: Query<(&Table, Option<&TableResults>), With<BalanceCounter>>,
+    mut level_stopwatch: ResMut<LevelStopwatch>,
+) {
+    let mut versus = match versus {
+        Some(versus) => versus,
+        None => return,
+    };
+    if versus.finished || tables.is_empty() || tables.iter().any(|(_, result)| result.is_none()) {
+        return;
+    }
+    versus.finished = true;
+    level_stopwatch.stop();
+
+    let mut results: Vec<(&Table, &TableResults)> = tables
+        .iter()
+        .filter_map(|(table, result)| Some((table, result?)))
+        .collect();
+    results.sort_by_key(|(table, _)| table.0);
+    let scores: Vec<(usize, f32)> = results
+        .iter()
+        .map(|(table, result)| (table.0, result.score))
+        .collect();
+
+    commands.insert_resource(LevelResults {
+        tables: results
+            .into_iter()
+            .map(|(_, result)| result.clone())
+            .collect(),
+        notes: vec![winner_text(&scores)],
+        elapsed_secs: level_stopwatch.stopwatch.elapsed_secs(),
+    });
+}
+
+fn start_system(versus: Option<ResMut<Versus>>) {
+    if let Some(mut versus) = versus {
+        versus.finished = false;
+    }
+}
+
+/// Versus is over once we're back on the menu
+fn stop_system(mut commands: Commands) {
+    commands.remove_resource::<Versus>();
+}
+
+pub struct VersusPlugin;
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(start_system))
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(stop_system));
+        timestep::add_tick_systems(
+            app,
+            SystemSet::on_update(GameState::Game)
+                .with_system(results_system.after(TickLabel::Results)),
+        );
+    }
+}
+
+#[test]
+fn test_winner_text() {
+    assert_eq!("Player 2 wins!", winner_text(&[(0, 40.0), (1, 92.5)]));
+    assert_eq!("It's a draw!", winner_text(&[(0, 80.0), (1, 80.0)]));
+}
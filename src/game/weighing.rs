@@ -0,0 +1,143 @@
+use crate::game::balance::{BalanceCounter, BalanceText};
+use crate::game::ball::{Ball, BallKind};
+use crate::game::table::Table;
+use crate::game::timestep::TICK;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+// Balls are only weighed once they've sat still on the balance for a moment, this avoids
+// counting them mid-air as they fall through the sensor.
+const SETTLED_SPEED: f32 = 4.0;
+const NUDGED_SPEED: f32 = 12.0;
+const SETTLE_TIME: Duration = Duration::from_millis(400);
+
+#[derive(Component)]
+pub struct WeighingZone;
+
+#[derive(Debug)]
+struct Candidate {
+    kind: BallKind,
+    settle_timer: Timer,
+    counted: bool,
+}
+
+impl Candidate {
+    fn new(kind: BallKind) -> Self {
+        Self {
+            kind,
+            settle_timer: Timer::new(SETTLE_TIME, false),
+            counted: false,
+        }
+    }
+}
+
+/// Balls currently inside a balance's [WeighingZone], whether or not they've settled yet. Kept
+/// on the balance
+#[derive(Component, Debug, Default)]
+pub struct WeighingCandidates(HashMap<Entity, Candidate>);
+
+fn other_entity_if_match(match_entity: &Entity, e1: Entity, e2: Entity) -> Option<Entity> {
+    if e1 == *match_entity {
+        Some(e2)
+    } else if e2 == *match_entity {
+        Some(e1)
+    } else {
+        None
+    }
+}
+
+/// Weighs each balance's zone into its own [BalanceCounter]
+pub fn weighing_system(
+    rapier_context: Res<RapierContext>,
+    zone_query: Query<(Entity, &Parent), With<WeighingZone>>,
+    ball_query: Query<(&Ball, &Velocity)>,
+    mut balance_query: Query<(&Table, &mut BalanceCounter, &mut WeighingCandidates)>,
+    mut balance_text_query: Query<(&Table, &mut Text), With<BalanceText>>,
+) {
+    for (zone_entity, parent) in zone_query.iter() {
+        let (table, mut balance_counter, mut candidates) = match balance_query.get_mut(parent.get())
+        {
+            Ok(balance) => balance,
+            Err(_) => continue,
+        };
+        if !weigh(
+            zone_entity,
+            &rapier_context,
+            &ball_query,
+            &mut candidates,
+            &mut balance_counter,
+        ) {
+            continue;
+        }
+
+        debug!("Counter changed: {:?}", *balance_counter);
+        let total_weight = balance_counter.total_weight();
+        for (text_table, mut text) in balance_text_query.iter_mut() {
+            if text_table == table {
+                text.sections[0].value = format!("{:.2}", total_weight);
+            }
+        }
+    }
+}
+
+/// Returns whether the count changed
+fn weigh(
+    zone_entity: Entity,
+    rapier_context: &RapierContext,
+    ball_query: &Query<(&Ball, &Velocity)>,
+    candidates: &mut WeighingCandidates,
+    balance_counter: &mut BalanceCounter,
+) -> bool {
+    let in_zone: HashSet<Entity> = rapier_context
+        .intersections_with(zone_entity)
+        .filter(|(_, _, intersecting)| *intersecting)
+        .filter_map(|(e1, e2, _)| other_entity_if_match(&zone_entity, e1, e2))
+        .filter(|entity| ball_query.get(*entity).is_ok())
+        .collect();
+
+    let mut counter_changed = false;
+
+    // Anything that's left the zone (or been despawned) comes off the balance
+    candidates.0.retain(|entity, candidate| {
+        let still_in_zone = in_zone.contains(entity);
+        if !still_in_zone && candidate.counted {
+            balance_counter.decr(candidate.kind.clone());
+            counter_changed = true;
+        }
+        still_in_zone
+    });
+
+    for entity in in_zone.into_iter() {
+        let (ball, velocity) = match ball_query.get(entity) {
+            Ok(ball) => ball,
+            Err(_) => continue,
+        };
+        let candidate = candidates
+            .0
+            .entry(entity)
+            .or_insert_with(|| Candidate::new(ball.0.clone()));
+        let speed = velocity.linvel.length();
+
+        if candidate.counted {
+            if speed > NUDGED_SPEED {
+                candidate.counted = false;
+                candidate.settle_timer.reset();
+                balance_counter.decr(candidate.kind.clone());
+                counter_changed = true;
+            }
+        } else if speed < SETTLED_SPEED {
+            candidate.settle_timer.tick(TICK);
+            if candidate.settle_timer.finished() {
+                candidate.counted = true;
+                balance_counter.incr(candidate.kind.clone());
+                counter_changed = true;
+            }
+        } else {
+            candidate.settle_timer.reset();
+        }
+    }
+
+    counter_changed
+}
@@ -0,0 +1,217 @@
+use crate::game::balance::BalanceCounter;
+use crate::game::bot::Bot;
+use crate::game::campaign::{Campaign, CampaignProgress, CurrentLevel};
+use crate::game::control::{ControlInput, KeyBindings};
+use crate::game::goals::{Countdown, LevelCriteria, LevelStopwatch};
+use crate::game::ingredients::{BallKindRegistry, IngredientsAsset, DEFAULT_INGREDIENTS_PATH};
+use crate::game::level::{self, LevelDef};
+use crate::game::replay::{Replay, LAST_REPLAY_KEY};
+use crate::game::table::Table;
+use crate::game::timestep::{ClockMode, SimClock, TimestepPlugin};
+use crate::game::{BallKind, GamePlugin};
+use crate::loading::LevelArg;
+use crate::manifest::{AssetGroup, BallSprites, ButtonImages, Fonts, Sprites};
+use crate::save::Save;
+use crate::{GameState, TotalScore};
+use bevy::asset::{AssetPlugin, FileAssetIo};
+use bevy::hierarchy::HierarchyPlugin;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
+use bevy_rapier2d::prelude::*;
+
+pub enum HeadlessLevel {
+    /// A level file, relative to the assets folder
+    File(String),
+    /// A random level rolled from this seed
+    Seed(u64),
+}
+
+fn read_asset(path: &str) -> Result<Vec<u8>, String> {
+    let full_path = FileAssetIo::get_base_path().join("assets").join(path);
+    std::fs::read(&full_path).map_err(|e| format!("Can't read {:?}: {}", full_path, e))
+}
+
+/// The game simulation without a window, audio or keyboard. Nothing moves until it's stepped,
+/// and inputs are set directly rather than read from the keyboard, so runs are repeatable
+pub struct HeadlessGame {
+    app: App,
+    /// The level's one balance, which holds its count and countdown
+    balance: Entity,
+}
+
+impl HeadlessGame {
+    /// Starts with the goals overlay dismissed, so the level clock runs from the first tick
+    pub fn new(level: HeadlessLevel) -> Result<Self, String> {
+        let mut game = Self::with_goals_shown(level)?;
+        game.dismiss_goals();
+        Ok(game)
+    }
+
+    /// Leaves the goals overlay up, and the level clock stopped, until a dismiss input's given.
+    /// For playing recorded inputs, which dismiss it when the player did
+    pub fn with_goals_shown(level: HeadlessLevel) -> Result<Self, String> {
+        let ingredients = IngredientsAsset::from_bytes(&read_asset(DEFAULT_INGREDIENTS_PATH)?)
+            .map_err(|e| e.to_string())?;
+        let registry = BallKindRegistry::from_asset(&ingredients);
+        let (resolved, current_level, level_arg) = match level {
+            HeadlessLevel::File(path) => {
+                let def = LevelDef::from_bytes(&read_asset(&path)?).map_err(|e| e.to_string())?;
+                let resolved = def.resolve(&registry).map_err(|e| e.to_string())?;
+                (resolved, CurrentLevel::Authored, LevelArg(Some(path)))
+            }
+            HeadlessLevel::Seed(seed) => (
+                level::new_random_level(&registry, seed),
+                CurrentLevel::Random(seed),
+                LevelArg(None),
+            ),
+        };
+        let (criteria, layout) = resolved;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(
+                RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(32f32)
+                    .with_default_system_setup(false),
+            )
+            .add_plugin(TimestepPlugin {
+                mode: ClockMode::Manual,
+            })
+            .insert_resource(TotalScore::from_scores(Vec::new()))
+            .insert_resource(CampaignProgress::default())
+            .insert_resource(KeyBindings::default())
+            .insert_resource(Campaign::default())
+            .insert_resource(Save::in_memory())
+            .insert_resource(level_arg)
+            .add_plugin(GamePlugin)
+            .insert_resource(registry)
+            .insert_resource(criteria)
+            .insert_resource(layout)
+            .insert_resource(current_level)
+            .add_state(GameState::Game);
+
+        // Loading's skipped, so the manifest's handed over before the level's entered
+        let asset_server = app.world.resource::<AssetServer>().clone();
+        let ball_sprites = BallSprites::load(
+            &asset_server,
+            app.world
+                .resource::<BallKindRegistry>()
+                .kinds()
+                .iter()
+                .map(|kind| kind.sprite()),
+        );
+        app.insert_resource(Fonts::load(&asset_server))
+            .insert_resource(Sprites::load(&asset_server))
+            .insert_resource(ButtonImages::load(&asset_server))
+            .insert_resource(ball_sprites);
+
+        // Enter the level
+        app.update();
+        let balance = app
+            .world
+            .query_filtered::<Entity, With<BalanceCounter>>()
+            .iter(&app.world)
+            .next()
+            .ok_or("The level has no balance")?;
+
+        Ok(Self { app, balance })
+    }
+
+    /// Held until it's set again. A dismiss that hasn't been seen by a tick yet is kept
+    pub fn set_input(&mut self, input: ControlInput) {
+        let mut control = self.app.world.resource_mut::<ControlInput>();
+        let dismiss = control.dismiss;
+        *control = input;
+        control.dismiss |= dismiss;
+    }
+
+    pub fn dismiss_goals(&mut self) {
+        self.app.world.resource_mut::<ControlInput>().dismiss = true;
+    }
+
+    /// Hands the controls to the bot. Inputs set after this are overwritten each tick
+    pub fn enable_bot(&mut self) {
+        self.app.world.insert_resource(Bot(Table(0)));
+    }
+
+    pub fn step(&mut self, ticks: u32) {
+        self.app.world.resource_mut::<SimClock>().advance(ticks);
+        self.app.update();
+    }
+
+    /// Ticks run since the level started
+    pub fn ticks(&self) -> u64 {
+        self.app.world.resource::<SimClock>().ticks()
+    }
+
+    pub fn balance(&self) -> &BalanceCounter {
+        self.app.world.get(self.balance).unwrap()
+    }
+
+    pub fn countdown(&self) -> &Countdown {
+        self.app.world.get(self.balance).unwrap()
+    }
+
+    pub fn total_score(&self) -> &TotalScore {
+        self.app.world.resource()
+    }
+
+    pub fn criteria(&self) -> &LevelCriteria {
+        self.app.world.resource()
+    }
+
+    /// Time on the level clock, which doesn't run while the goals overlay is up
+    pub fn elapsed_secs(&self) -> f32 {
+        self.app
+            .world
+            .resource::<LevelStopwatch>()
+            .stopwatch
+            .elapsed_secs()
+    }
+
+    /// The countdown has run out and the mix has been scored
+    pub fn finished(&self) -> bool {
+        matches!(
+            self.countdown(),
+            Countdown::Active {
+                end_calculated: true,
+                ..
+            }
+        )
+    }
+
+    /// Ratios and score for what's on the balance right now
+    pub fn results(&self) -> (Vec<String>, f32) {
+        let criteria = self.criteria();
+        self.balance()
+            .ratios_and_score(&criteria.target_mix, criteria.mix_measure)
+    }
+
+    pub fn kind(&self, name: &str) -> Option<BallKind> {
+        self.app
+            .world
+            .resource::<BallKindRegistry>()
+            .get(name)
+            .cloned()
+    }
+
+    /// The replay recorded when the level finished, runs are kept in memory rather than saved
+    pub fn last_replay(&self) -> Option<Replay> {
+        let contents = self
+            .app
+            .world
+            .resource::<Save>()
+            .read_extra(LAST_REPLAY_KEY)
+            .ok()??;
+        Replay::from_ron(&contents).ok()
+    }
+
+    /// For anything not covered above
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+}
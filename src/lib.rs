@@ -0,0 +1,260 @@
+use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy::render::texture::ImageSettings;
+
+use crate::save::Settings;
+use crate::ui_core::buttons::{
+    CheckboxEvent, CheckboxState, SliderEvent, TextInputFocusLabel, WidgetApp,
+};
+use bevy_kira_audio::{Audio, AudioApp, AudioChannel, AudioControl, AudioTween};
+use bevy_rapier2d::prelude::*;
+use std::fmt::Formatter;
+
+pub mod game;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+mod loading;
+mod manifest;
+mod menu;
+mod music;
+pub mod save;
+mod ui_core;
+
+#[derive(Hash, Clone, PartialOrd, PartialEq, Debug, Eq)]
+pub enum GameState {
+    Loading,
+    /// Something the game can't do without failed to load, see [loading::LoadFailures]
+    LoadError,
+    Menu,
+    LevelSelect,
+    Controls,
+    Game,
+    /// Pushed on top of [GameState::Game], which is left as it is underneath
+    Paused,
+    Results,
+    /// Either on its own from the menu or pushed on top of [GameState::Paused]
+    Settings,
+}
+
+#[cfg(target_arch = "wasm32")]
+const WINDOW_WIDTH: f32 = 960f32;
+#[cfg(not(target_arch = "wasm32"))]
+const WINDOW_WIDTH: f32 = 1280f32;
+
+#[cfg(target_arch = "wasm32")]
+const WINDOW_HEIGHT: f32 = 540f32;
+#[cfg(not(target_arch = "wasm32"))]
+const WINDOW_HEIGHT: f32 = 720f32;
+
+/// Runs the game in a window, everything below the window and audio can also be run headless,
+/// see [headless::HeadlessGame]
+pub fn run() {
+    // Physics is stepped on the game's fixed tick, see `TimestepPlugin`
+    let rapier: RapierPhysicsPlugin<NoUserData> =
+        RapierPhysicsPlugin::pixels_per_meter(32f32).with_default_system_setup(false);
+    App::new()
+        .insert_resource(ImageSettings::default_nearest())
+        .add_plugins(DefaultPlugins)
+        .add_plugin(bevy_kira_audio::AudioPlugin)
+        .add_plugin(rapier)
+        .add_plugin(game::timestep::TimestepPlugin {
+            mode: game::timestep::ClockMode::RealTime,
+        })
+        .add_audio_channel::<MusicChannel>()
+        .add_audio_channel::<SfxChannel>()
+        .add_plugin(save::SavePlugin)
+        .add_plugin(loading::LoadingPlugin)
+        .add_plugin(game::GamePlugin)
+        .add_plugin(game::GameAudioPlugin)
+        .add_plugin(game::PlayerControlPlugin)
+        .add_plugin(menu::MenuPlugin)
+        .add_plugin(music::MusicPlugin)
+        .add_plugin(FeatureEnabledPlugin)
+        .add_state(GameState::Loading)
+        .add_checkbox::<AudioToggle>()
+        .add_slider::<VolumeSlider>()
+        .add_system(ui_core::buttons::button_system)
+        .add_system(ui_core::buttons::text_input_focus_system.label(TextInputFocusLabel))
+        .init_resource::<ui_core::buttons::GamepadFocus>()
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            ui_core::buttons::gamepad_focus_system.after(bevy::ui::UiSystem::Focus),
+        )
+        .add_system(audio_setting_system.label(AudioSettingLabel))
+        .add_system(apply_audio_settings.after(AudioSettingLabel))
+        .add_system(apply_window_settings)
+        .add_startup_system(setup)
+        .run();
+}
+
+fn setup(mut commands: Commands, _asset_server: Res<AssetServer>) {
+    let camera_scale = if cfg!(target_arch = "wasm32") {
+        0.6
+    } else {
+        0.4
+    };
+    commands.spawn_bundle(Camera2dBundle {
+        projection: OrthographicProjection {
+            scale: camera_scale,
+            ..default()
+        },
+        ..default()
+    });
+}
+
+pub struct TotalScore {
+    scores: Vec<f32>,
+}
+
+impl TotalScore {
+    pub fn from_scores(scores: Vec<f32>) -> Self {
+        Self { scores }
+    }
+
+    pub fn scores(&self) -> &[f32] {
+        &self.scores
+    }
+
+    pub fn reset(&mut self) {
+        self.scores.clear()
+    }
+
+    pub fn total(&self) -> f32 {
+        self.scores.iter().sum()
+    }
+
+    pub fn mix_average(&self) -> f32 {
+        if self.scores.is_empty() {
+            0.0
+        } else {
+            self.total() / (self.scores.len() as f32)
+        }
+    }
+
+    pub fn add_score(&mut self, score: f32) {
+        self.scores.push(score);
+    }
+}
+
+impl std::fmt::Display for TotalScore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Total: {:.2} ({} Mixes. {:.2} Avg)",
+            self.total(),
+            self.scores.len(),
+            self.mix_average()
+        )
+    }
+}
+
+struct FeatureEnabledPlugin;
+impl Plugin for FeatureEnabledPlugin {
+    fn build(&self, app: &mut App) {
+        if cfg!(feature = "debug_fps") {
+            Self::diagnostics(app);
+        }
+
+        if cfg!(feature = "debug_render_colliders") {
+            Self::rapier_collider_render(app);
+        }
+    }
+}
+impl FeatureEnabledPlugin {
+    fn rapier_collider_render(app: &mut App) {
+        app.add_plugin(RapierDebugRenderPlugin::default());
+    }
+
+    fn diagnostics(app: &mut App) {
+        app.add_plugin(LogDiagnosticsPlugin::default())
+            .add_plugin(FrameTimeDiagnosticsPlugin::default());
+    }
+}
+
+/// At startup and whenever the settings change. On the web the page decides the mode, so only
+/// the starting size is set
+fn apply_window_settings(settings: Res<Settings>, mut windows: ResMut<Windows>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for window in windows.iter_mut() {
+        if cfg!(target_arch = "wasm32") {
+            if settings.is_added() {
+                window.set_resolution(WINDOW_WIDTH, WINDOW_HEIGHT);
+            }
+            continue;
+        }
+        let mode = settings.display_mode.to_window_mode();
+        if window.mode() != mode {
+            window.set_mode(mode);
+        }
+        let (width, height) = (settings.resolution.0 as f32, settings.resolution.1 as f32);
+        if window.requested_width() != width || window.requested_height() != height {
+            window.set_resolution(width, height);
+        }
+    }
+}
+
+#[derive(Component, Default, Clone)]
+pub struct MusicChannel;
+type MusicAudio = AudioChannel<MusicChannel>;
+
+#[derive(Component, Default, Clone)]
+pub struct SfxChannel;
+type SfxAudio = AudioChannel<SfxChannel>;
+
+/// Checkboxes on the menu that mute the music or sound effects
+#[derive(Component, Debug, Clone, Copy)]
+pub enum AudioToggle {
+    Music,
+    SFX,
+}
+
+/// Sliders on the settings screen, see [save::Settings::music_channel_volume]
+#[derive(Component, Debug, Clone, Copy)]
+pub enum VolumeSlider {
+    Master,
+    Music,
+    SFX,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+struct AudioSettingLabel;
+
+/// At startup and whenever the settings change
+fn apply_audio_settings(
+    settings: Res<Settings>,
+    music_channel: Res<MusicAudio>,
+    sfx_channel: Res<SfxAudio>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    music_channel.set_volume(settings.music_channel_volume() as f64);
+    sfx_channel.set_volume(settings.sfx_channel_volume() as f64);
+}
+
+fn audio_setting_system(
+    mut checkbox_events: EventReader<CheckboxEvent<AudioToggle>>,
+    mut slider_events: EventReader<SliderEvent<VolumeSlider>>,
+    mut settings: ResMut<Settings>,
+) {
+    for event in checkbox_events.iter() {
+        let enable = match event.new_state {
+            CheckboxState::Checked => true,
+            CheckboxState::Unchecked => false,
+        };
+        match event.widget {
+            AudioToggle::Music => settings.music_enabled = enable,
+            AudioToggle::SFX => settings.sfx_enabled = enable,
+        }
+    }
+    for event in slider_events.iter() {
+        let volume = match event.widget {
+            VolumeSlider::Master => &mut settings.master_volume,
+            VolumeSlider::Music => &mut settings.music_volume,
+            VolumeSlider::SFX => &mut settings.sfx_volume,
+        };
+        *volume = event.new_value;
+    }
+}
@@ -1,6 +1,13 @@
+use crate::game::audio::AudioAssetStore;
+use crate::game::campaign::{Campaign, CampaignAsset, CurrentLevel, CAMPAIGN_PATH};
+use crate::game::ingredients::{BallKindRegistry, IngredientsAsset, DEFAULT_INGREDIENTS_PATH};
+use crate::game::level::{self, LevelDef};
+use crate::game::replay::{ReplayLevel, ReplayPlayback};
+use crate::manifest::{AssetGroup, BallSprites, ButtonImages, Fonts, Sprites};
+use crate::music::{MusicCredits, Playlist, TrackCredit, MUSIC_CREDITS_PATH};
 use crate::ui_core::nodes;
 use crate::GameState;
-use bevy::asset::LoadState;
+use bevy::asset::{HandleId, LoadState};
 use bevy::prelude::*;
 use bevy_kira_audio::AudioSource;
 use nodes::Property;
@@ -10,21 +17,42 @@ pub struct LoadingPlugin;
 #[derive(Component)]
 pub struct LoadingOnlyMarker;
 
+/// The filled part of the progress bar
+#[derive(Component)]
+struct LoadingBar;
+
+const LOADING_BAR_WIDTH: f32 = 200.0;
+
+/// Level file to play instead of a random one, passed as `--level <path>` with a path
+/// relative to the assets folder
+pub struct LevelArg(pub Option<String>);
+
+impl LevelArg {
+    fn from_args() -> Self {
+        let mut args = std::env::args().skip_while(|arg| arg != "--level");
+        args.next();
+        Self(args.next())
+    }
+}
+
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_enter(GameState::Loading)
-                .with_system(loading_display_setup)
-                .with_system(start_loading_things),
-        )
-        .add_system_set(SystemSet::on_update(GameState::Loading).with_system(loading_watcher))
-        .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(teardown));
+        app.insert_resource(LevelArg::from_args())
+            .add_system_set(
+                SystemSet::on_enter(GameState::Loading)
+                    .with_system(loading_display_setup)
+                    .with_system(start_loading_things),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Loading).with_system(loading_watcher))
+            .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(teardown))
+            .add_system_set(
+                SystemSet::on_enter(GameState::LoadError).with_system(load_error_display_setup),
+            );
     }
 }
 
 fn loading_display_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     let bevy_icon = asset_server.load("bevy.png");
-    let font = asset_server.load("Quicksand-Bold.ttf");
 
     commands
         .spawn()
@@ -54,36 +82,73 @@ fn loading_display_setup(mut commands: Commands, asset_server: Res<AssetServer>)
 
                     parent
                         .spawn_bundle(nodes::new(vec![
-                            Property::Width(Val::Px(110.0)),
-                            Property::Height(Val::Px(200.0)),
-                            Property::MarginAll(Val::Auto),
+                            Property::Width(Val::Px(LOADING_BAR_WIDTH)),
+                            Property::Height(Val::Px(16.0)),
+                            Property::Margin(UiRect::new(
+                                Val::Auto,
+                                Val::Auto,
+                                Val::Px(20.0),
+                                Val::Auto,
+                            )),
+                            Property::Colour(Color::rgb(0.15, 0.15, 0.15)),
                         ]))
                         .with_children(|parent| {
-                            parent.spawn().insert_bundle(TextBundle {
-                                style: Style {
-                                    margin: UiRect::new(Val::Auto, Val::Auto, Val::Auto, Val::Auto),
-                                    ..default()
-                                },
-                                text: Text::from_section(
-                                    "Loading...",
-                                    TextStyle {
-                                        font,
-                                        font_size: 30.0,
-                                        ..default()
-                                    },
-                                )
-                                .with_alignment(TextAlignment::CENTER),
-                                ..default()
-                            });
+                            parent
+                                .spawn_bundle(nodes::new(vec![
+                                    Property::Width(Val::Percent(0.0)),
+                                    Property::Height(Val::Percent(100.0)),
+                                    Property::Colour(Color::WHITE),
+                                ]))
+                                .insert(LoadingBar);
                         });
                 });
         })
         .insert(LoadingOnlyMarker);
 }
 
-fn start_loading_things(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let audio = vec![asset_server.load("audio/music/Getting it Done.mp3")];
-    commands.insert_resource(LoadedHandles { audio });
+fn start_loading_things(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_arg: Res<LevelArg>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    let music_credits = MusicCredits::read(&asset_server);
+    let ingredients = asset_server.load(DEFAULT_INGREDIENTS_PATH);
+    let level_path = match playback.as_ref().map(|playback| &playback.0.level) {
+        Some(ReplayLevel::File(path)) => Some(path),
+        _ => level_arg.0.as_ref(),
+    };
+    let level = level_path.map(|path| asset_server.load(path.as_str()));
+    let campaign = asset_server.load(CAMPAIGN_PATH);
+
+    let fonts = Fonts::load(&asset_server);
+    let sprites = Sprites::load(&asset_server);
+    let button_images = ButtonImages::load(&asset_server);
+    let sfx = AudioAssetStore::load(&asset_server);
+    let mut manifest: Vec<(HandleId, Need)> = Vec::new();
+    for ids in [
+        fonts.handle_ids(),
+        sprites.handle_ids(),
+        button_images.handle_ids(),
+    ] {
+        manifest.extend(ids.into_iter().map(|id| (id, Need::Required)));
+    }
+    manifest.extend(sfx.handle_ids().into_iter().map(|id| (id, Need::Optional)));
+    commands.insert_resource(fonts);
+    commands.insert_resource(sprites);
+    commands.insert_resource(button_images);
+    commands.insert_resource(sfx);
+
+    commands.insert_resource(LoadedHandles {
+        music_credits,
+        music: None,
+        ingredients,
+        level,
+        campaign,
+        campaign_levels: None,
+        manifest,
+        ball_sprites: None,
+    });
 }
 
 fn teardown(mut commands: Commands, q: Query<Entity, With<LoadingOnlyMarker>>) {
@@ -93,23 +158,168 @@ fn teardown(mut commands: Commands, q: Query<Entity, With<LoadingOnlyMarker>>) {
 }
 
 struct LoadedHandles {
-    audio: Vec<Handle<AudioSource>>,
+    music_credits: MusicCredits,
+    /// Requested once the credits have loaded, as they list the tracks
+    music: Option<Vec<(TrackCredit, Handle<AudioSource>)>>,
+    ingredients: Handle<IngredientsAsset>,
+    level: Option<Handle<LevelDef>>,
+    campaign: Handle<CampaignAsset>,
+    /// Requested once the campaign itself has loaded
+    campaign_levels: Option<Vec<Handle<LevelDef>>>,
+    /// Everything in the manifest's resources, which hold the handles themselves
+    manifest: Vec<(HandleId, Need)>,
+    /// Requested once the ingredients have loaded
+    ball_sprites: Option<Vec<HandleId>>,
 }
 
 fn loading_watcher(
-    loaded_handles: Res<LoadedHandles>,
+    mut commands: Commands,
+    mut loaded_handles: ResMut<LoadedHandles>,
     asset_server: Res<AssetServer>,
+    ingredients_assets: Res<Assets<IngredientsAsset>>,
+    level_assets: Res<Assets<LevelDef>>,
+    campaign_assets: Res<Assets<CampaignAsset>>,
+    level_arg: Res<LevelArg>,
+    playback: Option<Res<ReplayPlayback>>,
     mut state: ResMut<State<GameState>>,
+    mut bars: Query<&mut Style, With<LoadingBar>>,
 ) {
-    let mut count = LoadStateCount::default();
-    for handle in loaded_handles.audio.iter() {
-        let load_state = asset_server.get_load_state(handle);
-        count.incr(&load_state);
+    let track = |report: &mut LoadReport, id: HandleId, need: Need| {
+        report.track(asset_server.get_load_state(id), need, || {
+            asset_path(&asset_server, id)
+        });
+    };
+    let mut report = LoadReport::default();
+    for (id, need) in loaded_handles.manifest.iter() {
+        track(&mut report, *id, *need);
+    }
+    report.track(
+        loaded_handles.music_credits.load_state(),
+        Need::Optional,
+        || MUSIC_CREDITS_PATH.to_string(),
+    );
+    if loaded_handles.music.is_none() {
+        if let Some(tracks) = loaded_handles.music_credits.tracks() {
+            let music = tracks
+                .iter()
+                .map(|credit| (credit.clone(), asset_server.load(credit.path().as_str())))
+                .collect();
+            loaded_handles.music = Some(music);
+        }
+    }
+    for (_, handle) in loaded_handles.music.iter().flatten() {
+        track(&mut report, handle.id, Need::Optional);
+    }
+    track(&mut report, loaded_handles.ingredients.id, Need::Required);
+    if loaded_handles.ball_sprites.is_none() {
+        if let Some(ingredients) = ingredients_assets.get(&loaded_handles.ingredients) {
+            let ball_sprites = BallSprites::load(
+                &asset_server,
+                ingredients.kinds.iter().map(|def| def.sprite.as_str()),
+            );
+            loaded_handles.ball_sprites = Some(ball_sprites.handle_ids());
+            commands.insert_resource(ball_sprites);
+        }
+    }
+    for id in loaded_handles.ball_sprites.iter().flatten() {
+        track(&mut report, *id, Need::Required);
+    }
+    // The level and campaign have their own fallbacks below
+    if let Some(level) = loaded_handles.level.as_ref() {
+        track(&mut report, level.id, Need::Optional);
+    }
+    track(&mut report, loaded_handles.campaign.id, Need::Optional);
+    if loaded_handles.campaign_levels.is_none() {
+        if let Some(campaign) = campaign_assets.get(&loaded_handles.campaign) {
+            let levels = campaign
+                .levels
+                .iter()
+                .map(|entry| asset_server.load(entry.level.as_str()))
+                .collect();
+            loaded_handles.campaign_levels = Some(levels);
+        }
+    }
+    for level in loaded_handles.campaign_levels.iter().flatten() {
+        track(&mut report, level.id, Need::Optional);
+    }
+
+    for mut style in bars.iter_mut() {
+        style.size.width = Val::Percent(report.count.progress() * 100.0);
     }
 
-    if count.all_finished() {
-        info!("Finished Loading: {:?}", count);
-        state.set(GameState::Menu).unwrap();
+    if report.count.all_finished() {
+        info!("Finished Loading: {:?}", report.count);
+        for path in report.optional_failures.iter() {
+            warn!("Failed to load {}, carrying on without it", path);
+        }
+        if report.is_blocked() {
+            for path in report.required_failures.iter() {
+                error!("Failed to load {}", path);
+            }
+            commands.insert_resource(LoadFailures(report.required_failures));
+            state.set(GameState::LoadError).unwrap();
+            return;
+        }
+
+        let ingredients = ingredients_assets
+            .get(&loaded_handles.ingredients)
+            .unwrap_or_else(|| panic!("Failed to load {}", DEFAULT_INGREDIENTS_PATH));
+        let registry = BallKindRegistry::from_asset(ingredients);
+
+        // Anything wrong with the level is logged and we fall back to a random level
+        let mut level_loaded = false;
+        if let Some(handle) = loaded_handles.level.as_ref() {
+            match level_assets.get(handle).map(|def| def.resolve(&registry)) {
+                Some(Ok(resolved)) => {
+                    level::insert_level(&mut commands, resolved);
+                    commands.insert_resource(CurrentLevel::Authored);
+                    level_loaded = true;
+                }
+                Some(Err(e)) => error!("{}", e),
+                None => error!("Failed to load level {:?}", level_arg.0),
+            }
+        }
+
+        // A replay goes straight into its level, or back to the menu if it can't be played
+        let play_replay = match playback.as_ref().map(|playback| &playback.0.level) {
+            Some(ReplayLevel::Seed(seed)) => {
+                level::insert_level(&mut commands, level::new_random_level(&registry, *seed));
+                commands.insert_resource(CurrentLevel::Random(*seed));
+                true
+            }
+            Some(ReplayLevel::File(_)) => level_loaded,
+            None => false,
+        };
+        if playback.is_some() && !play_replay {
+            commands.remove_resource::<ReplayPlayback>();
+        }
+
+        commands.insert_resource(Campaign::from_loaded(
+            campaign_assets.get(&loaded_handles.campaign),
+            loaded_handles
+                .campaign_levels
+                .as_deref()
+                .unwrap_or_default(),
+            &level_assets,
+            &registry,
+        ));
+        commands.insert_resource(registry);
+        commands.insert_resource(Playlist::new(
+            loaded_handles
+                .music
+                .take()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(_, handle)| {
+                    matches!(asset_server.get_load_state(handle), LoadState::Loaded)
+                })
+                .collect(),
+        ));
+        if play_replay {
+            state.set(GameState::Game).unwrap();
+        } else {
+            state.set(GameState::Menu).unwrap();
+        }
     }
 }
 
@@ -128,7 +338,7 @@ impl LoadStateCount {
             LoadState::NotLoaded => self.not_loaded += 1,
             LoadState::Loading => self.loading += 1,
             LoadState::Loaded => self.loaded += 1,
-            LoadState::Failed => self.loaded += 1,
+            LoadState::Failed => self.failed += 1,
             LoadState::Unloaded => self.unloaded += 1,
         }
     }
@@ -136,4 +346,137 @@ impl LoadStateCount {
     fn all_finished(&self) -> bool {
         self.not_loaded == 0 && self.loading == 0
     }
+
+    /// Fraction of what's been asked for so far that's done. Assets requested in a later
+    /// phase can pull it back a little
+    fn progress(&self) -> f32 {
+        let total = self.not_loaded + self.loading + self.loaded + self.failed + self.unloaded;
+        if total == 0 {
+            0.0
+        } else {
+            (self.loaded + self.failed) as f32 / total as f32
+        }
+    }
+}
+
+/// Whether the game can go on without an asset
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Need {
+    /// Loading stops on the error screen if it fails
+    Required,
+    /// Logged and left out if it fails
+    Optional,
+}
+
+/// How loading's going, and which assets failed
+#[derive(Default, Debug)]
+struct LoadReport {
+    count: LoadStateCount,
+    required_failures: Vec<String>,
+    optional_failures: Vec<String>,
+}
+
+impl LoadReport {
+    fn track(&mut self, load_state: LoadState, need: Need, path: impl FnOnce() -> String) {
+        if let LoadState::Failed = load_state {
+            match need {
+                Need::Required => self.required_failures.push(path()),
+                Need::Optional => self.optional_failures.push(path()),
+            }
+        }
+        self.count.incr(&load_state);
+    }
+
+    /// Something the game can't do without failed
+    fn is_blocked(&self) -> bool {
+        !self.required_failures.is_empty()
+    }
+}
+
+fn asset_path(asset_server: &AssetServer, id: HandleId) -> String {
+    asset_server
+        .get_handle_path(id)
+        .map(|path| path.path().display().to_string())
+        .unwrap_or_else(|| format!("{:?}", id))
+}
+
+/// Paths of the required assets that failed to load
+pub struct LoadFailures(Vec<String>);
+
+/// Lists what failed. If it's a font that failed the text can't be shown, but it's all in the
+/// log as well
+fn load_error_display_setup(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    failures: Res<LoadFailures>,
+) {
+    let text_style = TextStyle {
+        font: fonts.regular.clone(),
+        font_size: 20.0,
+        color: Color::WHITE,
+    };
+    let mut lines = vec![TextSection::new(
+        "Some of the game's files couldn't be loaded:\n",
+        TextStyle {
+            font: fonts.bold.clone(),
+            font_size: 30.0,
+            color: Color::WHITE,
+        },
+    )];
+    for path in failures.0.iter() {
+        lines.push(TextSection::new(format!("{}\n", path), text_style.clone()));
+    }
+    lines.push(TextSection::new(
+        "Check the assets folder is complete and restart the game",
+        text_style,
+    ));
+
+    commands
+        .spawn_bundle(nodes::new(nodes::defaults::full(
+            FlexDirection::Column,
+            Some(vec![Property::Justify(JustifyContent::Center)]),
+        )))
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: UiRect::all(Val::Auto),
+                    ..default()
+                },
+                text: Text::from_sections(lines).with_alignment(TextAlignment::CENTER),
+                ..default()
+            });
+        });
+}
+
+#[test]
+fn test_failed_loads_are_reported() {
+    let mut report = LoadReport::default();
+    report.track(LoadState::Loaded, Need::Required, || "cup.png".to_string());
+    report.track(LoadState::Failed, Need::Optional, || {
+        "audio/music/Getting it Done.mp3".to_string()
+    });
+    assert!(report.count.all_finished());
+    assert_eq!(1.0, report.count.progress());
+    assert_eq!(1, report.count.failed);
+    // Missing music isn't worth stopping for
+    assert!(!report.is_blocked());
+
+    report.track(LoadState::Loading, Need::Required, || {
+        "table.png".to_string()
+    });
+    assert!(!report.count.all_finished());
+    assert_eq!(2.0 / 3.0, report.count.progress());
+
+    report.track(LoadState::Failed, Need::Required, || {
+        "Quicksand-Bold.ttf".to_string()
+    });
+    assert!(report.is_blocked());
+    assert_eq!(
+        vec!["Quicksand-Bold.ttf".to_string()],
+        report.required_failures
+    );
+    assert_eq!(
+        vec!["audio/music/Getting it Done.mp3".to_string()],
+        report.optional_failures
+    );
 }
@@ -0,0 +1,151 @@
+//! Everything the game shows, loaded while [GameState::Loading](crate::GameState::Loading) so
+//! nothing pops in the first time it's used. Spawners take their handles from these resources
+//! rather than loading by path
+
+use bevy::asset::HandleId;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// A set of assets loaded together and kept as a resource
+pub trait AssetGroup: Send + Sync + 'static {
+    fn load(asset_server: &AssetServer) -> Self
+    where
+        Self: Sized;
+
+    /// Every handle in the group, for watching how loading is going
+    fn handle_ids(&self) -> Vec<HandleId>;
+}
+
+pub struct Fonts {
+    pub regular: Handle<Font>,
+    pub bold: Handle<Font>,
+}
+
+impl AssetGroup for Fonts {
+    fn load(asset_server: &AssetServer) -> Self {
+        Self {
+            regular: asset_server.load("Quicksand-Regular.ttf"),
+            bold: asset_server.load("Quicksand-Bold.ttf"),
+        }
+    }
+
+    fn handle_ids(&self) -> Vec<HandleId> {
+        vec![self.regular.id, self.bold.id]
+    }
+}
+
+/// Images for everything on the table
+pub struct Sprites {
+    pub background: Handle<Image>,
+    pub table: Handle<Image>,
+    pub balance: Handle<Image>,
+    pub cup: Handle<Image>,
+    pub tank: Handle<Image>,
+    pub tank_door: Handle<Image>,
+    /// Key helpers shown by the containers
+    pub helper_left: Handle<Image>,
+    pub helper_right: Handle<Image>,
+    pub helper_centre: Handle<Image>,
+    pub helper_jar: Handle<Image>,
+}
+
+impl AssetGroup for Sprites {
+    fn load(asset_server: &AssetServer) -> Self {
+        Self {
+            background: asset_server.load("background.png"),
+            table: asset_server.load("table.png"),
+            balance: asset_server.load("balance.png"),
+            cup: asset_server.load("cup.png"),
+            tank: asset_server.load("tank.png"),
+            tank_door: asset_server.load("tank_door.png"),
+            helper_left: asset_server.load("ui_helper_l.png"),
+            helper_right: asset_server.load("ui_helper_r.png"),
+            helper_centre: asset_server.load("ui_helper_2.png"),
+            helper_jar: asset_server.load("ui_helper_jar_l.png"),
+        }
+    }
+
+    fn handle_ids(&self) -> Vec<HandleId> {
+        vec![
+            self.background.id,
+            self.table.id,
+            self.balance.id,
+            self.cup.id,
+            self.tank.id,
+            self.tank_door.id,
+            self.helper_left.id,
+            self.helper_right.id,
+            self.helper_centre.id,
+            self.helper_jar.id,
+        ]
+    }
+}
+
+/// Images for image buttons and checkboxes
+pub struct ButtonImages {
+    pub play: Handle<Image>,
+    pub quit: Handle<Image>,
+    pub reset_score: Handle<Image>,
+    pub exit: Handle<Image>,
+    pub restart: Handle<Image>,
+    pub music_checked: Handle<Image>,
+    pub music_unchecked: Handle<Image>,
+    pub sfx_checked: Handle<Image>,
+    pub sfx_unchecked: Handle<Image>,
+}
+
+impl AssetGroup for ButtonImages {
+    fn load(asset_server: &AssetServer) -> Self {
+        Self {
+            play: asset_server.load("buttons/play.png"),
+            quit: asset_server.load("buttons/quit.png"),
+            reset_score: asset_server.load("buttons/reset_score.png"),
+            exit: asset_server.load("buttons/exit.png"),
+            restart: asset_server.load("buttons/restart.png"),
+            music_checked: asset_server.load("checkbox/music_checked.png"),
+            music_unchecked: asset_server.load("checkbox/music_unchecked.png"),
+            sfx_checked: asset_server.load("checkbox/sfx_checked.png"),
+            sfx_unchecked: asset_server.load("checkbox/sfx_unchecked.png"),
+        }
+    }
+
+    fn handle_ids(&self) -> Vec<HandleId> {
+        vec![
+            self.play.id,
+            self.quit.id,
+            self.reset_score.id,
+            self.exit.id,
+            self.restart.id,
+            self.music_checked.id,
+            self.music_unchecked.id,
+            self.sfx_checked.id,
+            self.sfx_unchecked.id,
+        ]
+    }
+}
+
+/// Each ingredient's ball sprite, by path. Which sprites there are depends on the ingredients
+/// file, so these are loaded once it has been
+#[derive(Default)]
+pub struct BallSprites(HashMap<String, Handle<Image>>);
+
+impl BallSprites {
+    pub fn load<'a>(asset_server: &AssetServer, paths: impl Iterator<Item = &'a str>) -> Self {
+        Self(
+            paths
+                .map(|path| (path.to_string(), asset_server.load(path)))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, path: &str) -> Handle<Image> {
+        self.0.get(path).cloned().unwrap_or_else(|| {
+            warn!("Ball sprite {} wasn't loaded up front", path);
+            Handle::default()
+        })
+    }
+
+    pub fn handle_ids(&self) -> Vec<HandleId> {
+        self.0.values().map(|handle| handle.id).collect()
+    }
+}
@@ -0,0 +1,231 @@
+use crate::game::control::{Action, KeyBindings};
+use crate::manifest::Fonts;
+use crate::ui_core::buttons;
+use crate::ui_core::nodes;
+use crate::GameState;
+use bevy::prelude::*;
+use nodes::Property;
+
+pub struct ControlsPlugin;
+
+#[derive(Component, Clone)]
+struct ControlsOnlyMarker;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Rebinding(None))
+            .add_system_set(SystemSet::on_enter(GameState::Controls).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(GameState::Controls)
+                    .with_system(button_system)
+                    .with_system(rebind_system)
+                    .with_system(binding_text_system),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Controls).with_system(cleanup));
+    }
+}
+
+/// The action waiting for a key press, if any
+struct Rebinding(Option<Action>);
+
+#[derive(Component)]
+enum ControlsButton {
+    Binding(Action),
+    Defaults,
+    Back,
+}
+
+const BINDING_FONT_SIZE: f32 = 20.0;
+const CONFLICT_COLOUR: Color = Color::rgb(0.9, 0.3, 0.3);
+
+fn keys_text(keys: &[KeyCode]) -> String {
+    if keys.is_empty() {
+        return "Unbound".to_string();
+    }
+    keys.iter()
+        .map(|key| format!("{:?}", key))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+fn binding_text(action: Action, bindings: &KeyBindings, rebinding: &Rebinding) -> String {
+    if rebinding.0 == Some(action) {
+        return format!("{}: press a key (Escape cancels)", action);
+    }
+    let text = format!("{}: {}", action, keys_text(bindings.keys(action)));
+    match bindings.conflicts(action).first() {
+        Some(other) => format!("{} - also {}", text, other),
+        None => text,
+    }
+}
+
+fn setup(mut commands: Commands, fonts: Res<Fonts>, mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+    let font = fonts.bold.clone();
+    let binding_size = Size::new(Val::Px(480.0), Val::Px(34.0));
+    let column = || {
+        nodes::new(vec![
+            Property::MarginAll(Val::Auto),
+            Property::Height(Val::Auto),
+            Property::Width(Val::Auto),
+            Property::Direction(FlexDirection::ColumnReverse),
+        ])
+    };
+    let (cup_actions, other_actions): (Vec<Action>, Vec<Action>) =
+        Action::all().into_iter().partition(|action| {
+            matches!(
+                action,
+                Action::TiltAnticlockwise(_) | Action::TiltClockwise(_)
+            )
+        });
+
+    commands
+        .spawn_bundle(nodes::new(nodes::defaults::full(
+            FlexDirection::ColumnReverse,
+            Some(vec![Property::Justify(JustifyContent::Center)]),
+        )))
+        .insert(ControlsOnlyMarker)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: buttons::rect_consts::CENTRED,
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Controls",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+
+            parent
+                .spawn_bundle(nodes::new(vec![
+                    Property::MarginAll(Val::Auto),
+                    Property::Height(Val::Auto),
+                    Property::Width(Val::Auto),
+                    Property::Direction(FlexDirection::Row),
+                ]))
+                .with_children(|parent| {
+                    for actions in [cup_actions, other_actions] {
+                        parent.spawn_bundle(column()).with_children(|parent| {
+                            for action in actions {
+                                buttons::make_text_button(
+                                    ControlsButton::Binding(action),
+                                    action.to_string(),
+                                    binding_size,
+                                    parent,
+                                    font.clone(),
+                                );
+                            }
+                        });
+                    }
+                });
+
+            parent
+                .spawn_bundle(nodes::new(vec![
+                    Property::MarginAll(Val::Auto),
+                    Property::Height(Val::Auto),
+                    Property::Width(Val::Auto),
+                    Property::Direction(FlexDirection::Row),
+                ]))
+                .with_children(|parent| {
+                    buttons::make_text_button(
+                        ControlsButton::Defaults,
+                        "Defaults",
+                        Size::new(Val::Px(200.0), Val::Px(50.0)),
+                        parent,
+                        font.clone(),
+                    );
+                    buttons::make_text_button(
+                        ControlsButton::Back,
+                        "Back",
+                        Size::new(Val::Px(150.0), Val::Px(50.0)),
+                        parent,
+                        font.clone(),
+                    );
+                });
+        });
+}
+
+fn button_system(
+    buttons: Query<(&ControlsButton, &Interaction), Changed<Interaction>>,
+    mut state: ResMut<State<GameState>>,
+    mut bindings: ResMut<KeyBindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for (button, interaction) in buttons.iter() {
+        if let Interaction::Clicked = interaction {
+            match button {
+                ControlsButton::Binding(action) => rebinding.0 = Some(*action),
+                ControlsButton::Defaults => {
+                    *bindings = KeyBindings::default();
+                    rebinding.0 = None;
+                }
+                ControlsButton::Back => state.set(GameState::Menu).unwrap(),
+            }
+        }
+    }
+}
+
+/// Binds the next key pressed to the action being rebound
+fn rebind_system(
+    input: Res<Input<KeyCode>>,
+    mut bindings: ResMut<KeyBindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    let action = match rebinding.0 {
+        Some(action) => action,
+        None => return,
+    };
+    if let Some(key) = input.get_just_pressed().next() {
+        if *key != KeyCode::Escape {
+            bindings.set(action, *key);
+        }
+        rebinding.0 = None;
+    }
+}
+
+fn binding_text_system(
+    bindings: Res<KeyBindings>,
+    rebinding: Res<Rebinding>,
+    fonts: Res<Fonts>,
+    buttons: Query<(&ControlsButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    if !bindings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (button, children) in buttons.iter() {
+        let action = match button {
+            ControlsButton::Binding(action) => *action,
+            _ => continue,
+        };
+        let color = if bindings.conflicts(action).is_empty() {
+            Color::rgb(0.9, 0.9, 0.9)
+        } else {
+            CONFLICT_COLOUR
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                *text = Text::from_section(
+                    binding_text(action, &bindings, &rebinding),
+                    TextStyle {
+                        font: fonts.bold.clone(),
+                        font_size: BINDING_FONT_SIZE,
+                        color,
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, entities: Query<Entity, With<ControlsOnlyMarker>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
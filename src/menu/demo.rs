@@ -0,0 +1,99 @@
+use crate::game::bot::{Bot, Demo};
+use crate::game::goals::LevelResults;
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level;
+use crate::game::seed::LevelRng;
+use crate::game::table::Table;
+use crate::GameState;
+use bevy::input::touch::Touches;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::time::Stopwatch;
+
+// Left alone on the menu for this long, the bot starts playing
+const IDLE_SECS: f32 = 20.0;
+
+/// The menu's auto-play. After a while on the menu without any input the bot plays a random
+/// level, until there's input again or the level's over
+pub struct DemoPlugin;
+
+impl Plugin for DemoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuIdle>()
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(reset_idle))
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(start_system))
+            // Before anything in the level can act on the same input
+            .add_system_to_stage(CoreStage::PreUpdate, stop_system.after(InputSystem));
+    }
+}
+
+#[derive(Default)]
+struct MenuIdle(Stopwatch);
+
+fn any_input(
+    keys: &Input<KeyCode>,
+    mouse: &Input<MouseButton>,
+    gamepad_buttons: &Input<GamepadButton>,
+    touches: &Touches,
+) -> bool {
+    keys.get_just_pressed().next().is_some()
+        || mouse.get_just_pressed().next().is_some()
+        || gamepad_buttons.get_just_pressed().next().is_some()
+        || touches.iter_just_pressed().next().is_some()
+}
+
+fn reset_idle(mut idle: ResMut<MenuIdle>) {
+    idle.0.reset();
+}
+
+fn start_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut idle: ResMut<MenuIdle>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    registry: Res<BallKindRegistry>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if cursor_moved.iter().count() > 0 || any_input(&keys, &mouse, &gamepad_buttons, &touches) {
+        idle.0.reset();
+        return;
+    }
+    idle.0.tick(time.delta());
+    if idle.0.elapsed_secs() < IDLE_SECS {
+        return;
+    }
+    // Its own seed, so the player's run of levels carries on as it was
+    level::insert_seeded_level(&mut commands, &registry, rand::random());
+    commands.insert_resource(Bot(Table(0)));
+    commands.insert_resource(Demo);
+    state.set(GameState::Game).unwrap();
+}
+
+/// Back to the menu with a fresh level for the player, so Play doesn't start the one the bot
+/// just played
+fn stop_system(
+    mut commands: Commands,
+    demo: Option<Res<Demo>>,
+    results: Option<Res<LevelResults>>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    registry: Res<BallKindRegistry>,
+    mut level_rng: ResMut<LevelRng>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if demo.is_none() || *state.current() != GameState::Game {
+        return;
+    }
+    if results.is_none() && !any_input(&keys, &mouse, &gamepad_buttons, &touches) {
+        return;
+    }
+    commands.remove_resource::<LevelResults>();
+    level::insert_random_level(&mut commands, &registry, &mut level_rng);
+    state.set(GameState::Menu).unwrap();
+}
@@ -0,0 +1,119 @@
+use crate::game::campaign::{Campaign, CampaignProgress};
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level::LevelDef;
+use crate::manifest::Fonts;
+use crate::ui_core::buttons;
+use crate::ui_core::nodes;
+use crate::GameState;
+use bevy::prelude::*;
+use nodes::Property;
+
+pub struct LevelSelectPlugin;
+
+#[derive(Component, Clone)]
+struct LevelSelectOnlyMarker;
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::LevelSelect).with_system(setup))
+            .add_system_set(SystemSet::on_update(GameState::LevelSelect).with_system(button_system))
+            .add_system_set(SystemSet::on_exit(GameState::LevelSelect).with_system(cleanup));
+    }
+}
+
+#[derive(Component)]
+enum LevelSelectButton {
+    Level(usize),
+    Locked,
+    Back,
+}
+
+fn level_button_text(index: usize, name: &str, best_score: Option<f32>) -> String {
+    match best_score {
+        Some(best_score) => format!("{}. {} - Best {:.2}", index + 1, name, best_score),
+        None => format!("{}. {}", index + 1, name),
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    campaign: Res<Campaign>,
+    progress: Res<CampaignProgress>,
+) {
+    let font = fonts.bold.clone();
+    let button_size = Size::new(Val::Px(520.0), Val::Px(50.0));
+
+    commands
+        .spawn_bundle(nodes::new(nodes::defaults::full(
+            FlexDirection::ColumnReverse,
+            Some(vec![Property::Justify(JustifyContent::Center)]),
+        )))
+        .insert(LevelSelectOnlyMarker)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: buttons::rect_consts::CENTRED,
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Campaign",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+
+            for (index, level) in campaign.levels().iter().enumerate() {
+                let (button, text) = if progress.is_unlocked(&campaign, index) {
+                    (
+                        LevelSelectButton::Level(index),
+                        level_button_text(index, &level.name, progress.best_score(level)),
+                    )
+                } else {
+                    (LevelSelectButton::Locked, format!("{}. Locked", index + 1))
+                };
+                buttons::make_text_button(button, text, button_size, parent, font.clone());
+            }
+
+            buttons::make_text_button(
+                LevelSelectButton::Back,
+                "Back",
+                Size::new(Val::Px(150.0), Val::Px(50.0)),
+                parent,
+                font.clone(),
+            );
+        });
+}
+
+fn button_system(
+    mut commands: Commands,
+    buttons: Query<(&LevelSelectButton, &Interaction), Changed<Interaction>>,
+    mut state: ResMut<State<GameState>>,
+    campaign: Res<Campaign>,
+    level_assets: Res<Assets<LevelDef>>,
+    registry: Res<BallKindRegistry>,
+) {
+    for (button, interaction) in buttons.iter() {
+        if let Interaction::Clicked = interaction {
+            match button {
+                LevelSelectButton::Level(index) => {
+                    if campaign.start_level(*index, &mut commands, &level_assets, &registry) {
+                        state.set(GameState::Game).unwrap();
+                    }
+                }
+                LevelSelectButton::Locked => (),
+                LevelSelectButton::Back => state.set(GameState::Menu).unwrap(),
+            }
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, entities: Query<Entity, With<LevelSelectOnlyMarker>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -4,11 +4,30 @@ use crate::{GameState, TotalScore};
 use bevy::app::AppExit;
 use bevy::prelude::*;
 
+use crate::game::bot::Bot;
+use crate::game::campaign::CurrentLevel;
+use crate::game::coop::Coop;
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level;
 use crate::game::not_a_cup::spawn_jar;
+use crate::game::seed::LevelRng;
+use crate::game::table::Table;
+use crate::game::versus::{self, Versus};
 use crate::game::BallKind;
-use crate::ui_core::buttons::CheckboxVariant;
+use crate::manifest::{ButtonImages, Fonts, Sprites};
+use crate::music::NowPlayingText;
+use crate::save::Settings;
+use crate::ui_core::buttons::{ButtonComponent, TextInput, TextInputEvent, WidgetApp};
+use crate::AudioToggle;
 use nodes::Property;
 
+mod controls;
+mod demo;
+mod level_select;
+mod pause;
+mod results;
+mod settings;
+
 pub struct MenuPlugin;
 
 #[derive(Component, Clone)]
@@ -21,11 +40,38 @@ impl Plugin for MenuPlugin {
                 .with_system(setup)
                 .with_system(debug_tank_setup),
         )
-        .add_system_set(SystemSet::on_update(GameState::Menu).with_system(button_system))
-        .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(cleanup));
+        .add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(button_system)
+                .with_system(seed_entry_system),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(cleanup))
+        .add_plugin(level_select::LevelSelectPlugin)
+        .add_plugin(controls::ControlsPlugin)
+        .add_plugin(demo::DemoPlugin)
+        .add_plugin(pause::PausePlugin)
+        .add_plugin(results::ResultsPlugin)
+        .add_plugin(settings::SettingsPlugin)
+        .add_text_input::<SeedInput>()
+        .insert_resource(SeedEntry(String::new()));
+    }
+}
+
+/// Seed typed in on the menu, Play rolls levels from it when set
+struct SeedEntry(String);
+
+// Enough digits to always fit in a u64
+const MAX_SEED_DIGITS: usize = 19;
+
+impl SeedEntry {
+    fn seed(&self) -> Option<u64> {
+        self.0.parse().ok()
     }
 }
 
+#[derive(Component, Clone)]
+struct SeedInput;
+
 const HALF_PANE: [Property; 4] = [
     Property::MarginAll(Val::Auto),
     Property::Height(Val::Percent(100.0)),
@@ -39,19 +85,22 @@ fn button_margin() -> UiRect<Val> {
     UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(10.0), Val::Px(50.0))
 }
 
-fn debug_tank_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    //spawn_jar(0.0, BallKind::Blue, &mut commands, &asset_server);
+fn debug_tank_setup(mut commands: Commands, sprites: Res<Sprites>) {
+    //spawn_jar(0.0, BallKind::Blue, &mut commands, &sprites);
 }
 
 fn setup(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    fonts: Res<Fonts>,
+    button_images: Res<ButtonImages>,
     windows: Res<Windows>,
     total_score: Res<TotalScore>,
+    settings: Res<Settings>,
+    seed_entry: Res<SeedEntry>,
 ) {
-    let play_image = asset_server.load("buttons/play.png");
-    let quit_image = asset_server.load("buttons/quit.png");
-    let reset_score_image = asset_server.load("buttons/reset_score.png");
+    let play_image = button_images.play.clone();
+    let quit_image = button_images.quit.clone();
+    let reset_score_image = button_images.reset_score.clone();
 
     println!("Menu Setup");
 
@@ -71,7 +120,7 @@ fn setup(
     };
 
     let left_text_style = TextStyle {
-        font: asset_server.load("Quicksand-Regular.ttf"),
+        font: fonts.regular.clone(),
         font_size,
         color: Default::default(),
     };
@@ -124,6 +173,79 @@ fn setup(
                                 Some(button_padding()),
                                 Some(button_margin()),
                             );
+                            buttons::make_button_custom_size(
+                                MenuButton::Campaign,
+                                Size::new(Val::Px(200.0), Val::Px(50.0)),
+                                parent,
+                                fonts.bold.clone(),
+                            );
+                            buttons::make_button_custom_size(
+                                MenuButton::Daily,
+                                Size::new(Val::Px(200.0), Val::Px(50.0)),
+                                parent,
+                                fonts.bold.clone(),
+                            );
+                            buttons::make_button_custom_size(
+                                MenuButton::Versus,
+                                Size::new(Val::Px(200.0), Val::Px(50.0)),
+                                parent,
+                                fonts.bold.clone(),
+                            );
+                            buttons::make_button_custom_size(
+                                MenuButton::VersusBot,
+                                Size::new(Val::Px(200.0), Val::Px(50.0)),
+                                parent,
+                                fonts.bold.clone(),
+                            );
+                            buttons::make_button_custom_size(
+                                MenuButton::Coop,
+                                Size::new(Val::Px(200.0), Val::Px(50.0)),
+                                parent,
+                                fonts.bold.clone(),
+                            );
+                            buttons::make_button_custom_size(
+                                MenuButton::Controls,
+                                Size::new(Val::Px(200.0), Val::Px(50.0)),
+                                parent,
+                                fonts.bold.clone(),
+                            );
+                            buttons::make_button_custom_size(
+                                MenuButton::Settings,
+                                Size::new(Val::Px(200.0), Val::Px(50.0)),
+                                parent,
+                                fonts.bold.clone(),
+                            );
+                            parent
+                                .spawn_bundle(nodes::new(vec![
+                                    Property::MarginAll(Val::Auto),
+                                    Property::Height(Val::Auto),
+                                    Property::Width(Val::Auto),
+                                    Property::Direction(FlexDirection::Row),
+                                ]))
+                                .with_children(|parent| {
+                                    parent.spawn_bundle(TextBundle {
+                                        style: Style {
+                                            margin: buttons::rect_consts::CENTRED,
+                                            ..default()
+                                        },
+                                        text: Text::from_section("Seed: ", left_text_style.clone()),
+                                        ..default()
+                                    });
+                                    // Focused from the start, so a seed can be typed straight in
+                                    let input = TextInput::new(
+                                        seed_entry.0.clone(),
+                                        MAX_SEED_DIGITS,
+                                        |c| c.is_ascii_digit(),
+                                    )
+                                    .focused();
+                                    buttons::make_text_input(
+                                        parent,
+                                        SeedInput,
+                                        input,
+                                        Size::new(Val::Px(440.0), Val::Px(50.0)),
+                                        fonts.bold.clone(),
+                                    );
+                                });
                             buttons::make_button_custom_image(
                                 MenuButton::Reset,
                                 reset_score_image,
@@ -153,13 +275,17 @@ fn setup(
                                 .with_children(|parent| {
                                     buttons::make_checkbox(
                                         parent,
-                                        CheckboxVariant::Music,
-                                        &asset_server,
+                                        AudioToggle::Music,
+                                        settings.music_enabled.into(),
+                                        button_images.music_checked.clone(),
+                                        button_images.music_unchecked.clone(),
                                     );
                                     buttons::make_checkbox(
                                         parent,
-                                        CheckboxVariant::SFX,
-                                        &asset_server,
+                                        AudioToggle::SFX,
+                                        settings.sfx_enabled.into(),
+                                        button_images.sfx_checked.clone(),
+                                        button_images.sfx_unchecked.clone(),
                                     );
                                 });
                         });
@@ -178,8 +304,22 @@ fn setup(
         .with_children(|parent| {
             parent.spawn_bundle(TextBundle::from_section(
                 format!("{}", *total_score),
-                left_text_style,
+                left_text_style.clone(),
             ));
+            // Filled in with whatever the playlist's on
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section("", left_text_style).with_style(Style {
+                        margin: UiRect::new(
+                            Val::Px(40.0),
+                            Val::Undefined,
+                            Val::Undefined,
+                            Val::Undefined,
+                        ),
+                        ..default()
+                    }),
+                )
+                .insert(NowPlayingText);
         });
 }
 
@@ -199,20 +339,92 @@ const LEFT_TEXT: [&str; 10] = [
 #[derive(Component)]
 pub enum MenuButton {
     Play,
+    Campaign,
+    Daily,
+    Versus,
+    VersusBot,
+    Coop,
+    Controls,
+    Settings,
     Quit,
     Reset,
 }
 
+impl ButtonComponent for MenuButton {
+    fn to_text(&self) -> &'static str {
+        match self {
+            MenuButton::Play => "Play",
+            MenuButton::Campaign => "Campaign",
+            MenuButton::Daily => "Daily Challenge",
+            MenuButton::Versus => "Versus",
+            MenuButton::VersusBot => "Versus Bot",
+            MenuButton::Coop => "Co-op",
+            MenuButton::Controls => "Controls",
+            MenuButton::Settings => "Settings",
+            MenuButton::Quit => "Quit",
+            MenuButton::Reset => "Reset",
+        }
+    }
+}
+
 pub fn button_system(
+    mut commands: Commands,
     buttons: Query<(&MenuButton, &Interaction), Changed<Interaction>>,
     mut state: ResMut<State<GameState>>,
     mut exit: EventWriter<AppExit>,
     mut total_score: ResMut<TotalScore>,
+    current_level: Res<CurrentLevel>,
+    registry: Res<BallKindRegistry>,
+    mut level_rng: ResMut<LevelRng>,
+    seed_entry: Res<SeedEntry>,
 ) {
     for (button, interaction) in buttons.iter() {
         match interaction {
             Interaction::Clicked => match button {
-                MenuButton::Play => state.set(GameState::Game).unwrap(),
+                MenuButton::Play => {
+                    if let Some(seed) = seed_entry.seed() {
+                        // The seed typed in is the level's own, levels after it carry on from it
+                        *level_rng = LevelRng::from_seed(seed);
+                        level::insert_seeded_level(&mut commands, &registry, seed);
+                    } else if let CurrentLevel::Campaign(_) = *current_level {
+                        // Free play shouldn't carry on from a campaign level
+                        level::insert_random_level(&mut commands, &registry, &mut level_rng);
+                    }
+                    state.set(GameState::Game).unwrap()
+                }
+                MenuButton::Campaign => state.set(GameState::LevelSelect).unwrap(),
+                MenuButton::Daily => {
+                    *level_rng = LevelRng::daily();
+                    level::insert_random_level(&mut commands, &registry, &mut level_rng);
+                    state.set(GameState::Game).unwrap()
+                }
+                MenuButton::Versus | MenuButton::VersusBot => {
+                    let seeded = seed_entry.seed().map_or(false, |seed| {
+                        *level_rng = LevelRng::from_seed(seed);
+                        versus::insert_seeded_versus_level(&mut commands, &registry, seed)
+                    });
+                    if !seeded {
+                        versus::insert_versus_level(&mut commands, &registry, &mut level_rng);
+                    }
+                    commands.insert_resource(Versus::default());
+                    if let MenuButton::VersusBot = button {
+                        // Playing player two's table
+                        commands.insert_resource(Bot(Table(1)));
+                    }
+                    state.set(GameState::Game).unwrap()
+                }
+                MenuButton::Coop => {
+                    if let Some(seed) = seed_entry.seed() {
+                        *level_rng = LevelRng::from_seed(seed);
+                        level::insert_seeded_level(&mut commands, &registry, seed);
+                    } else {
+                        level::insert_random_level(&mut commands, &registry, &mut level_rng);
+                    }
+                    commands.insert_resource(Coop);
+                    state.set(GameState::Game).unwrap()
+                }
+                MenuButton::Controls => state.set(GameState::Controls).unwrap(),
+                MenuButton::Settings => state.set(GameState::Settings).unwrap(),
                 MenuButton::Quit => exit.send(AppExit),
                 MenuButton::Reset => {
                     total_score.reset();
@@ -224,6 +436,15 @@ pub fn button_system(
     }
 }
 
+fn seed_entry_system(
+    mut events: EventReader<TextInputEvent<SeedInput>>,
+    mut seed_entry: ResMut<SeedEntry>,
+) {
+    for event in events.iter() {
+        seed_entry.0 = event.value.clone();
+    }
+}
+
 fn cleanup(mut commands: Commands, entities: Query<Entity, With<MenuOnlyMarker>>) {
     for entity in entities.iter() {
         commands.entity(entity).despawn_recursive();
@@ -0,0 +1,170 @@
+use crate::game::campaign::CurrentLevel;
+use crate::game::control::{self, KeyBindings};
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level;
+use crate::game::versus::Versus;
+use crate::manifest::Fonts;
+use crate::ui_core::buttons::{self, ButtonComponent};
+use crate::ui_core::nodes;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use nodes::Property;
+
+/// Pausing pushes [GameState::Paused] over the level, so everything on the level's tick stops
+/// where it is: the stopwatch, countdowns, ball spawning and the containers. Physics runs on the
+/// tick whatever the state, so it's switched off separately
+pub struct PausePlugin;
+
+#[derive(Component, Clone)]
+struct PauseOnlyMarker;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(pause_toggle_system)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused)
+                    .with_system(setup)
+                    .with_system(freeze_physics),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(button_system))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused)
+                    .with_system(cleanup)
+                    .with_system(unfreeze_physics),
+            )
+            // Out of the way while the settings screen's pushed on top
+            .add_system_set(SystemSet::on_pause(GameState::Paused).with_system(cleanup))
+            .add_system_set(SystemSet::on_resume(GameState::Paused).with_system(setup));
+    }
+}
+
+#[derive(Component)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Settings,
+    Exit,
+}
+
+impl ButtonComponent for PauseButton {
+    fn to_text(&self) -> &'static str {
+        match self {
+            PauseButton::Resume => "Resume",
+            PauseButton::Restart => "Restart",
+            PauseButton::Settings => "Settings",
+            PauseButton::Exit => "Exit",
+        }
+    }
+}
+
+/// Outside of any state's systems, so a press that pauses can't also resume in the same frame
+fn pause_toggle_system(
+    input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if !control::pause_just_pressed(&input, &bindings, &gamepads, &gamepad_buttons) {
+        return;
+    }
+    match state.current() {
+        GameState::Game => state.push(GameState::Paused).unwrap(),
+        GameState::Paused => state.pop().unwrap(),
+        _ => (),
+    }
+}
+
+fn freeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn unfreeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+fn setup(mut commands: Commands, fonts: Res<Fonts>) {
+    let font = fonts.bold.clone();
+    let button_size = Size::new(Val::Px(200.0), Val::Px(50.0));
+
+    commands
+        .spawn_bundle(nodes::new(nodes::defaults::full(
+            FlexDirection::ColumnReverse,
+            Some(vec![
+                Property::PositionType(PositionType::Absolute),
+                Property::Justify(JustifyContent::Center),
+                Property::Colour(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+            ]),
+        )))
+        .insert(PauseOnlyMarker)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: buttons::rect_consts::CENTRED,
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Paused",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+
+            parent
+                .spawn_bundle(nodes::new(vec![
+                    Property::MarginAll(Val::Auto),
+                    Property::Height(Val::Auto),
+                    Property::Width(Val::Auto),
+                    Property::Direction(FlexDirection::ColumnReverse),
+                ]))
+                .with_children(|parent| {
+                    for button in [
+                        PauseButton::Resume,
+                        PauseButton::Restart,
+                        PauseButton::Settings,
+                        PauseButton::Exit,
+                    ] {
+                        buttons::make_button_custom_size(button, button_size, parent, font.clone());
+                    }
+                });
+        });
+}
+
+fn button_system(
+    mut commands: Commands,
+    buttons: Query<(&PauseButton, &Interaction), Changed<Interaction>>,
+    mut state: ResMut<State<GameState>>,
+    registry: Res<BallKindRegistry>,
+    current_level: Res<CurrentLevel>,
+    versus: Option<Res<Versus>>,
+) {
+    for (button, interaction) in buttons.iter() {
+        if let Interaction::Clicked = interaction {
+            match button {
+                PauseButton::Resume => state.pop().unwrap(),
+                PauseButton::Restart => {
+                    level::insert_same_level(
+                        &mut commands,
+                        &registry,
+                        &current_level,
+                        versus.as_deref(),
+                    );
+                    state.replace(GameState::Game).unwrap();
+                }
+                PauseButton::Settings => state.push(GameState::Settings).unwrap(),
+                PauseButton::Exit => state.replace(GameState::Menu).unwrap(),
+            }
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, entities: Query<Entity, With<PauseOnlyMarker>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
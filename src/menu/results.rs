@@ -0,0 +1,304 @@
+use crate::game::balance::PartResult;
+use crate::game::bot::Demo;
+use crate::game::campaign::{Campaign, CampaignProgress, CurrentLevel};
+use crate::game::goals::LevelResults;
+use crate::game::ingredients::BallKindRegistry;
+use crate::game::level::{self, LevelDef};
+use crate::game::seed::LevelRng;
+use crate::game::versus::{self, Versus};
+use crate::manifest::Fonts;
+use crate::ui_core::buttons::{self, ButtonComponent};
+use crate::ui_core::nodes;
+use crate::GameState;
+use bevy::prelude::*;
+use nodes::Property;
+
+/// Once a level's scored the game moves on to [GameState::Results], which stays up until a
+/// button's pressed
+pub struct ResultsPlugin;
+
+#[derive(Component, Clone)]
+struct ResultsOnlyMarker;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Game).with_system(show_system))
+            .add_system_set(SystemSet::on_enter(GameState::Results).with_system(setup))
+            .add_system_set(SystemSet::on_update(GameState::Results).with_system(button_system))
+            .add_system_set(SystemSet::on_exit(GameState::Results).with_system(cleanup));
+    }
+}
+
+#[derive(Component)]
+enum ResultsButton {
+    Next,
+    Retry,
+    Menu,
+}
+
+impl ButtonComponent for ResultsButton {
+    fn to_text(&self) -> &'static str {
+        match self {
+            ResultsButton::Next => "Next",
+            ResultsButton::Retry => "Retry",
+            ResultsButton::Menu => "Menu",
+        }
+    }
+}
+
+// Width of a bar for the whole mix
+const FULL_BAR_WIDTH: f32 = 300.0;
+const BAR_HEIGHT: f32 = 12.0;
+
+/// The menu's demo goes back to the menu instead, see [super::demo]
+fn show_system(
+    results: Option<Res<LevelResults>>,
+    demo: Option<Res<Demo>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if results.is_some() && demo.is_none() {
+        state.set(GameState::Results).unwrap();
+    }
+}
+
+/// Whether there's a level to go on to after this one: another roll for random levels, the
+/// next campaign level once it's unlocked
+fn has_next(
+    current_level: &CurrentLevel,
+    campaign: &Campaign,
+    progress: &CampaignProgress,
+) -> bool {
+    match *current_level {
+        CurrentLevel::Random(_) => true,
+        CurrentLevel::Authored => false,
+        CurrentLevel::Campaign(index) => {
+            campaign.get(index + 1).is_some() && progress.is_unlocked(campaign, index + 1)
+        }
+    }
+}
+
+fn time_text(secs: f32) -> String {
+    let secs = secs.floor();
+    format!(
+        "Time: {:02}:{:02}",
+        secs.div_euclid(60.0),
+        secs.rem_euclid(60.0)
+    )
+}
+
+fn bar(parent: &mut ChildBuilder, pct: f32, colour: Color) {
+    parent.spawn_bundle(nodes::new(vec![
+        Property::Width(Val::Px(FULL_BAR_WIDTH * pct.clamp(0.0, 100.0) / 100.0)),
+        Property::Height(Val::Px(BAR_HEIGHT)),
+        Property::Margin(UiRect::new(
+            Val::Px(0.0),
+            Val::Auto,
+            Val::Px(2.0),
+            Val::Px(2.0),
+        )),
+        Property::Colour(colour),
+    ]));
+}
+
+fn text(parent: &mut ChildBuilder, value: String, text_style: TextStyle, style: Style) {
+    parent.spawn_bundle(TextBundle {
+        style,
+        text: Text::from_section(value, text_style),
+        ..default()
+    });
+}
+
+fn centred_text(parent: &mut ChildBuilder, value: String, text_style: TextStyle) {
+    let style = Style {
+        margin: buttons::rect_consts::CENTRED,
+        ..default()
+    };
+    text(parent, value, text_style, style);
+}
+
+/// The ingredient's name, its goal and actual shares as bars, and what it scored
+fn part_row(parent: &mut ChildBuilder, part: &PartResult, text_style: &TextStyle) {
+    let column = |width: f32| Style {
+        size: Size::new(Val::Px(width), Val::Auto),
+        margin: UiRect::all(Val::Px(4.0)),
+        ..default()
+    };
+    let colour = part.kind.to_color();
+    let mut goal_colour = colour;
+    goal_colour.set_a(0.4);
+
+    parent
+        .spawn_bundle(nodes::new(vec![
+            Property::MarginAll(Val::Auto),
+            Property::Height(Val::Auto),
+            Property::Width(Val::Auto),
+            Property::Direction(FlexDirection::Row),
+        ]))
+        .with_children(|parent| {
+            text(
+                parent,
+                part.kind.to_string(),
+                text_style.clone(),
+                column(100.0),
+            );
+            parent
+                .spawn_bundle(nodes::new(vec![
+                    Property::Height(Val::Auto),
+                    Property::Width(Val::Px(FULL_BAR_WIDTH)),
+                    Property::Direction(FlexDirection::ColumnReverse),
+                    Property::Justify(JustifyContent::Center),
+                ]))
+                .with_children(|parent| {
+                    bar(parent, part.target, goal_colour);
+                    bar(parent, part.actual, colour);
+                });
+            text(
+                parent,
+                format!(
+                    "Goal {:.2}, Actual {:.2}: {:.2} / 50",
+                    part.target, part.actual, part.score
+                ),
+                text_style.clone(),
+                column(280.0),
+            );
+        });
+}
+
+fn setup(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    results: Res<LevelResults>,
+    current_level: Res<CurrentLevel>,
+    campaign: Res<Campaign>,
+    progress: Res<CampaignProgress>,
+) {
+    let font = fonts.bold.clone();
+    let text_style = TextStyle {
+        font: fonts.regular.clone(),
+        font_size: 20.0,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn_bundle(nodes::new(nodes::defaults::full(
+            FlexDirection::ColumnReverse,
+            Some(vec![Property::Justify(JustifyContent::Center)]),
+        )))
+        .insert(ResultsOnlyMarker)
+        .with_children(|parent| {
+            centred_text(
+                parent,
+                "Results".to_string(),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            );
+
+            for table in results.tables.iter() {
+                for part in table.parts.iter() {
+                    part_row(parent, part, &text_style);
+                }
+                centred_text(
+                    parent,
+                    format!("{}: {:.2}", table.label, table.score),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                    },
+                );
+                centred_text(
+                    parent,
+                    format!(
+                        "Balls weighed: {} of {} spawned",
+                        table.balls_weighed, table.balls_spawned
+                    ),
+                    text_style.clone(),
+                );
+            }
+
+            for note in results.notes.iter() {
+                centred_text(parent, note.clone(), text_style.clone());
+            }
+            centred_text(parent, time_text(results.elapsed_secs), text_style.clone());
+
+            parent
+                .spawn_bundle(nodes::new(vec![
+                    Property::MarginAll(Val::Auto),
+                    Property::Height(Val::Auto),
+                    Property::Width(Val::Auto),
+                    Property::Direction(FlexDirection::Row),
+                ]))
+                .with_children(|parent| {
+                    let button_size = Size::new(Val::Px(150.0), Val::Px(50.0));
+                    if has_next(&current_level, &campaign, &progress) {
+                        buttons::make_button_custom_size(
+                            ResultsButton::Next,
+                            button_size,
+                            parent,
+                            font.clone(),
+                        );
+                    }
+                    buttons::make_button_custom_size(
+                        ResultsButton::Retry,
+                        button_size,
+                        parent,
+                        font.clone(),
+                    );
+                    buttons::make_button_custom_size(
+                        ResultsButton::Menu,
+                        button_size,
+                        parent,
+                        font.clone(),
+                    );
+                });
+        });
+}
+
+fn button_system(
+    mut commands: Commands,
+    buttons: Query<(&ResultsButton, &Interaction), Changed<Interaction>>,
+    mut state: ResMut<State<GameState>>,
+    current_level: Res<CurrentLevel>,
+    campaign: Res<Campaign>,
+    level_assets: Res<Assets<LevelDef>>,
+    registry: Res<BallKindRegistry>,
+    mut level_rng: ResMut<LevelRng>,
+    versus: Option<Res<Versus>>,
+) {
+    for (button, interaction) in buttons.iter() {
+        if let Interaction::Clicked = interaction {
+            match button {
+                ResultsButton::Next => {
+                    let started = match *current_level {
+                        CurrentLevel::Campaign(index) => {
+                            campaign.start_level(index + 1, &mut commands, &level_assets, &registry)
+                        }
+                        _ if versus.is_some() => {
+                            versus::insert_versus_level(&mut commands, &registry, &mut level_rng);
+                            true
+                        }
+                        _ => {
+                            level::insert_random_level(&mut commands, &registry, &mut level_rng);
+                            true
+                        }
+                    };
+                    if started {
+                        state.set(GameState::Game).unwrap();
+                    }
+                }
+                // The level's criteria and layout are still in place
+                ResultsButton::Retry => state.set(GameState::Game).unwrap(),
+                ResultsButton::Menu => state.set(GameState::Menu).unwrap(),
+            }
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, entities: Query<Entity, With<ResultsOnlyMarker>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<LevelResults>();
+}
@@ -0,0 +1,233 @@
+use crate::manifest::Fonts;
+use crate::save::{DisplayMode, Settings};
+use crate::ui_core::buttons::{self, SelectorEvent, WidgetApp};
+use crate::ui_core::nodes;
+use crate::GameState;
+use crate::VolumeSlider;
+use bevy::prelude::*;
+use nodes::Property;
+
+/// Volumes and the window, from the menu or pushed over the pause menu. Everything's applied
+/// as it's changed and saved with the rest of the settings
+pub struct SettingsPlugin;
+
+#[derive(Component, Clone)]
+struct SettingsOnlyMarker;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_selector::<WindowSelector>()
+            .add_system_set(SystemSet::on_enter(GameState::Settings).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(button_system)
+                    .with_system(window_selector_system),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Settings).with_system(cleanup));
+    }
+}
+
+#[derive(Component)]
+enum SettingsButton {
+    Back,
+}
+
+#[derive(Component, Clone)]
+enum WindowSelector {
+    DisplayMode,
+    /// The sizes on offer, see [resolution_options]
+    Resolution(Vec<(u32, u32)>),
+}
+
+const DISPLAY_MODES: [DisplayMode; 3] = [
+    DisplayMode::Windowed,
+    DisplayMode::BorderlessFullscreen,
+    DisplayMode::Fullscreen,
+];
+
+const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+const VOLUME_STEP: f32 = 0.05;
+
+/// The usual sizes, plus the saved one at the end if it's something else so it's labelled as
+/// what it is
+fn resolution_options(settings: &Settings) -> Vec<(u32, u32)> {
+    let mut resolutions = RESOLUTIONS.to_vec();
+    if !resolutions.contains(&settings.resolution) {
+        resolutions.push(settings.resolution);
+    }
+    resolutions
+}
+
+/// Options to pick from and which one's set
+fn window_options(selector: &WindowSelector, settings: &Settings) -> (Vec<String>, usize) {
+    match selector {
+        WindowSelector::DisplayMode => (
+            DISPLAY_MODES
+                .iter()
+                .map(|mode| format!("Window: {}", mode))
+                .collect(),
+            DISPLAY_MODES
+                .iter()
+                .position(|mode| *mode == settings.display_mode)
+                .unwrap_or(0),
+        ),
+        WindowSelector::Resolution(resolutions) => (
+            resolutions
+                .iter()
+                .map(|(width, height)| format!("Resolution: {}x{}", width, height))
+                .collect(),
+            resolutions
+                .iter()
+                .position(|resolution| *resolution == settings.resolution)
+                .unwrap_or(0),
+        ),
+    }
+}
+
+fn setup(mut commands: Commands, fonts: Res<Fonts>, settings: Res<Settings>) {
+    let font = fonts.bold.clone();
+    let label_style = TextStyle {
+        font: fonts.regular.clone(),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+    let row = || {
+        nodes::new(vec![
+            Property::MarginAll(Val::Auto),
+            Property::Height(Val::Auto),
+            Property::Width(Val::Auto),
+            Property::Direction(FlexDirection::Row),
+        ])
+    };
+
+    commands
+        .spawn_bundle(nodes::new(nodes::defaults::full(
+            FlexDirection::ColumnReverse,
+            Some(vec![
+                Property::Justify(JustifyContent::Center),
+                Property::Colour(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+            ]),
+        )))
+        .insert(SettingsOnlyMarker)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: buttons::rect_consts::CENTRED,
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Settings",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                ..default()
+            });
+
+            for (label, slider, value) in [
+                ("Master", VolumeSlider::Master, settings.master_volume),
+                ("Music", VolumeSlider::Music, settings.music_volume),
+                ("Sound effects", VolumeSlider::SFX, settings.sfx_volume),
+            ] {
+                parent.spawn_bundle(row()).with_children(|parent| {
+                    parent.spawn_bundle(TextBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(180.0), Val::Auto),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        text: Text::from_section(label, label_style.clone()),
+                        ..default()
+                    });
+                    buttons::make_slider(parent, slider, value, 0.0..=1.0, VOLUME_STEP);
+                });
+            }
+
+            // The page looks after the window on the web
+            if !cfg!(target_arch = "wasm32") {
+                parent.spawn_bundle(row()).with_children(|parent| {
+                    let resolutions = resolution_options(&settings);
+                    for selector in [
+                        WindowSelector::DisplayMode,
+                        WindowSelector::Resolution(resolutions),
+                    ] {
+                        let (options, index) = window_options(&selector, &settings);
+                        buttons::make_selector(
+                            parent,
+                            selector,
+                            options,
+                            index,
+                            Size::new(Val::Px(480.0), Val::Px(50.0)),
+                            font.clone(),
+                        );
+                    }
+                });
+            }
+
+            buttons::make_text_button(
+                SettingsButton::Back,
+                "Back",
+                Size::new(Val::Px(150.0), Val::Px(50.0)),
+                parent,
+                font.clone(),
+            );
+        });
+}
+
+fn button_system(
+    buttons: Query<(&SettingsButton, &Interaction), Changed<Interaction>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for (button, interaction) in buttons.iter() {
+        if let Interaction::Clicked = interaction {
+            match button {
+                // Back to the pause menu if that's where we came from
+                SettingsButton::Back => {
+                    if state.inactives().is_empty() {
+                        state.set(GameState::Menu).unwrap();
+                    } else {
+                        state.pop().unwrap();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn window_selector_system(
+    mut events: EventReader<SelectorEvent<WindowSelector>>,
+    mut settings: ResMut<Settings>,
+) {
+    for event in events.iter() {
+        match &event.widget {
+            WindowSelector::DisplayMode => settings.display_mode = DISPLAY_MODES[event.index],
+            WindowSelector::Resolution(resolutions) => {
+                settings.resolution = resolutions[event.index]
+            }
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, entities: Query<Entity, With<SettingsOnlyMarker>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[test]
+fn test_unlisted_resolution_is_its_own_option() {
+    let mut settings = Settings {
+        resolution: (1024, 768),
+        ..default()
+    };
+    let selector = WindowSelector::Resolution(resolution_options(&settings));
+    let (options, index) = window_options(&selector, &settings);
+    assert_eq!(RESOLUTIONS.len() + 1, options.len());
+    assert_eq!("Resolution: 1024x768", options[index]);
+
+    settings.resolution = RESOLUTIONS[1];
+    assert_eq!(RESOLUTIONS.to_vec(), resolution_options(&settings));
+    assert_eq!(1, window_options(&selector, &settings).1);
+}
@@ -0,0 +1,276 @@
+use crate::game::audio::AudioTriggerEvent;
+use crate::{GameState, MusicAudio};
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::tasks::IoTaskPool;
+use bevy_kira_audio::{
+    AudioControl, AudioEasing, AudioInstance, AudioSource, AudioTween, PlaybackState,
+};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Every track in here needs a credit, so the credits double as the track list
+pub const MUSIC_CREDITS_PATH: &str = "audio/music/ATTRIBUTIONS.txt";
+const MUSIC_DIR: &str = "audio/music";
+
+/// How much faster the music plays once a countdown's started
+const TENSE_PLAYBACK_RATE: f64 = 1.25;
+const PLAYBACK_RATE_TWEEN: Duration = Duration::from_millis(800);
+
+/// Shuffles whatever music loaded and speeds it up while the countdown's running
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(playlist_system)
+            .add_system(tense_music_system)
+            .add_system(now_playing_system)
+            .add_system_set(SystemSet::on_enter(GameState::Results).with_system(calm_music))
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(calm_music))
+            .add_system_set(SystemSet::on_enter(GameState::Game).with_system(calm_music));
+    }
+}
+
+/// A track and who to credit for it, the track is `<title>.mp3` next to the credits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackCredit {
+    pub title: String,
+    /// The whole attribution as it's written in the file
+    pub credit: String,
+}
+
+impl TrackCredit {
+    pub fn path(&self) -> String {
+        format!("{}/{}.mp3", MUSIC_DIR, self.title)
+    }
+
+    /// Just the first line, e.g. `"Getting it Done" Kevin MacLeod (incompetech.com)`
+    pub fn headline(&self) -> &str {
+        self.credit.lines().next().unwrap_or(&self.title)
+    }
+}
+
+/// Credits are separated by blank lines and start with the track's title in quotes. Anything
+/// else in the file is skipped
+pub fn parse_credits(text: &str) -> Vec<TrackCredit> {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter_map(|block| {
+            let block = block.trim();
+            let title = block.strip_prefix('"')?.split('"').next()?;
+            Some(TrackCredit {
+                title: title.to_string(),
+                credit: block.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The credits file, read straight through the asset server's IO. Asset loaders are picked by
+/// extension, so a loader for it would claim every `.txt` in the game
+#[derive(Clone, Default)]
+pub struct MusicCredits(Arc<Mutex<Option<Result<Vec<TrackCredit>, String>>>>);
+
+impl MusicCredits {
+    pub fn read(asset_server: &AssetServer) -> Self {
+        let credits = Self::default();
+        let result = credits.0.clone();
+        let asset_server = asset_server.clone();
+        IoTaskPool::get()
+            .spawn(async move {
+                let read = asset_server
+                    .asset_io()
+                    .load_path(Path::new(MUSIC_CREDITS_PATH))
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string()))
+                    .map(|text| parse_credits(&text));
+                if let Err(e) = &read {
+                    warn!("Failed to read {}: {}", MUSIC_CREDITS_PATH, e);
+                }
+                *result.lock().unwrap() = Some(read);
+            })
+            .detach();
+        credits
+    }
+
+    pub fn load_state(&self) -> LoadState {
+        match self.0.lock().unwrap().as_ref() {
+            None => LoadState::Loading,
+            Some(Ok(_)) => LoadState::Loaded,
+            Some(Err(_)) => LoadState::Failed,
+        }
+    }
+
+    /// The credits once they've been read
+    pub fn tracks(&self) -> Option<Vec<TrackCredit>> {
+        self.0.lock().unwrap().as_ref()?.as_ref().ok().cloned()
+    }
+}
+
+/// The tracks that loaded, played in a shuffled order that's reshuffled each time through
+pub struct Playlist {
+    tracks: Vec<(TrackCredit, Handle<AudioSource>)>,
+    order: Vec<usize>,
+    next: usize,
+    playing: Option<(usize, Handle<AudioInstance>)>,
+    tense: bool,
+}
+
+impl Playlist {
+    pub fn new(tracks: Vec<(TrackCredit, Handle<AudioSource>)>) -> Self {
+        Self {
+            tracks,
+            order: Vec::new(),
+            next: 0,
+            playing: None,
+            tense: false,
+        }
+    }
+
+    pub fn now_playing(&self) -> Option<&TrackCredit> {
+        self.playing
+            .as_ref()
+            .map(|(index, _)| &self.tracks[*index].0)
+    }
+
+    /// Doesn't play the same track twice in a row across a reshuffle unless it's the only one
+    fn next_track(&mut self, rng: &mut impl Rng) -> Option<usize> {
+        if self.tracks.is_empty() {
+            return None;
+        }
+        if self.next >= self.order.len() {
+            let last = self.order.last().copied();
+            self.order = (0..self.tracks.len()).collect();
+            self.order.shuffle(rng);
+            if self.order.len() > 1 && self.order.first().copied() == last {
+                let end = self.order.len() - 1;
+                self.order.swap(0, end);
+            }
+            self.next = 0;
+        }
+        self.next += 1;
+        Some(self.order[self.next - 1])
+    }
+
+    fn playback_rate(&self) -> f64 {
+        if self.tense {
+            TENSE_PLAYBACK_RATE
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Starts the next track once the last one's finished
+fn playlist_system(playlist: Option<ResMut<Playlist>>, audio: Res<MusicAudio>) {
+    let mut playlist = match playlist {
+        Some(playlist) => playlist,
+        None => return,
+    };
+    if let Some((_, instance)) = playlist.playing.as_ref() {
+        if !matches!(audio.state(instance), PlaybackState::Stopped) {
+            return;
+        }
+    }
+    if let Some(index) = playlist.next_track(&mut rand::thread_rng()) {
+        info!("Playing {}", playlist.tracks[index].0.title);
+        let instance = audio
+            .play(playlist.tracks[index].1.clone())
+            .with_playback_rate(playlist.playback_rate())
+            .handle();
+        playlist.playing = Some((index, instance));
+    }
+}
+
+fn set_tense(playlist: &mut Playlist, audio: &MusicAudio, tense: bool) {
+    if playlist.tense == tense {
+        return;
+    }
+    playlist.tense = tense;
+    audio
+        .set_playback_rate(playlist.playback_rate())
+        .fade_in(AudioTween::new(
+            PLAYBACK_RATE_TWEEN,
+            AudioEasing::InOutPowi(2),
+        ));
+}
+
+fn tense_music_system(
+    mut events: EventReader<AudioTriggerEvent>,
+    playlist: Option<ResMut<Playlist>>,
+    audio: Res<MusicAudio>,
+) {
+    let started = events
+        .iter()
+        .any(|event| matches!(event, AudioTriggerEvent::CountdownStarted));
+    if let (true, Some(mut playlist)) = (started, playlist) {
+        set_tense(&mut playlist, &audio, true);
+    }
+}
+
+/// Back to normal speed for the results, or if the level's left before it's finished
+fn calm_music(playlist: Option<ResMut<Playlist>>, audio: Res<MusicAudio>) {
+    if let Some(mut playlist) = playlist {
+        set_tense(&mut playlist, &audio, false);
+    }
+}
+
+/// Text showing the credit for what's playing, see [now_playing_system]
+#[derive(Component)]
+pub struct NowPlayingText;
+
+fn now_playing_system(
+    playlist: Option<Res<Playlist>>,
+    mut texts: Query<&mut Text, With<NowPlayingText>>,
+) {
+    let headline = playlist
+        .as_ref()
+        .and_then(|playlist| playlist.now_playing())
+        .map(|track| format!("Music: {}", track.headline()))
+        .unwrap_or_default();
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != headline {
+            text.sections[0].value = headline.clone();
+        }
+    }
+}
+
+#[test]
+fn test_credits_and_shuffle() {
+    let credits = parse_credits(
+        "\"Getting it Done\" Kevin MacLeod (incompetech.com)\r\n\
+         Licensed under Creative Commons: By Attribution 4.0 License\r\n\
+         \r\n\
+         Not a credit\n\
+         \n\
+         \"Robobozo\" Kevin MacLeod (incompetech.com)\n",
+    );
+    assert_eq!(credits.len(), 2);
+    assert_eq!(credits[0].title, "Getting it Done");
+    assert_eq!(credits[0].path(), "audio/music/Getting it Done.mp3");
+    assert_eq!(
+        credits[0].headline(),
+        "\"Getting it Done\" Kevin MacLeod (incompetech.com)"
+    );
+    assert_eq!(credits[1].title, "Robobozo");
+
+    let mut playlist = Playlist::new(
+        credits
+            .into_iter()
+            .map(|credit| (credit, Handle::default()))
+            .collect(),
+    );
+    let mut rng = rand::thread_rng();
+    let mut last = None;
+    for _ in 0..20 {
+        let index = playlist.next_track(&mut rng);
+        assert!(index.is_some());
+        assert_ne!(index, last);
+        last = index;
+    }
+    assert_eq!(Playlist::new(Vec::new()).next_track(&mut rng), None);
+}
@@ -0,0 +1,374 @@
+use crate::game::campaign::CampaignProgress;
+use crate::game::control::KeyBindings;
+use crate::TotalScore;
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const SAVE_KEY: &str = "save.ron";
+const SAVE_VERSION: u32 = 1;
+
+/// Somewhere to keep small text blobs between sessions
+pub trait SaveBackend: Send + Sync {
+    fn read(&self, key: &str) -> Result<Option<String>, String>;
+    fn write(&self, key: &str, contents: &str) -> Result<(), String>;
+}
+
+/// Files in `<config dir>/ball_combiner/`
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileBackend {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileBackend {
+    fn new() -> Option<Self> {
+        dirs::config_dir().map(|dir| Self {
+            dir: dir.join("ball_combiner"),
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveBackend for FileBackend {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        match std::fs::read_to_string(self.dir.join(key)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join(key), contents).map_err(|e| e.to_string())
+    }
+}
+
+/// The browser's localStorage, keys are prefixed so we don't trample anything else on the page
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorageBackend;
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorageBackend {
+    fn storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .ok_or("No window")?
+            .local_storage()
+            .map_err(|e| format!("{:?}", e))?
+            .ok_or_else(|| "No localStorage".into())
+    }
+
+    fn prefixed(key: &str) -> String {
+        format!("ball_combiner/{}", key)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SaveBackend for LocalStorageBackend {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        Self::storage()?
+            .get_item(&Self::prefixed(key))
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        Self::storage()?
+            .set_item(&Self::prefixed(key), contents)
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+/// Used when there's nowhere to save to, nothing is kept
+struct NoBackend;
+impl SaveBackend for NoBackend {
+    fn read(&self, _key: &str) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+
+    fn write(&self, _key: &str, _contents: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Kept until the game exits, for runs that shouldn't touch the player's save but still want
+/// to read back what was written
+#[derive(Default)]
+struct MemoryBackend(Mutex<HashMap<String, String>>);
+impl SaveBackend for MemoryBackend {
+    fn read(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<(), String> {
+        self.0.lock().unwrap().insert(key.into(), contents.into());
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn default_backend() -> Box<dyn SaveBackend> {
+    match FileBackend::new() {
+        Some(backend) => Box::new(backend),
+        None => {
+            warn!("No config directory, progress won't be saved");
+            Box::new(NoBackend)
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_backend() -> Box<dyn SaveBackend> {
+    Box::new(LocalStorageBackend)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    BorderlessFullscreen,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn to_window_mode(self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::BorderlessFullscreen => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::SizedFullscreen,
+        }
+    }
+}
+
+impl std::fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::BorderlessFullscreen => "Borderless",
+            DisplayMode::Fullscreen => "Fullscreen",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Mutes, from the checkboxes on the menu
+    pub music_enabled: bool,
+    pub sfx_enabled: bool,
+    /// From 0 to 1, music and sound effects are both scaled by the master volume
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// Ignored on the web, where the page decides
+    pub display_mode: DisplayMode,
+    pub resolution: (u32, u32),
+}
+
+impl Settings {
+    pub fn music_channel_volume(&self) -> f32 {
+        Self::channel_volume(self.music_enabled, self.master_volume * self.music_volume)
+    }
+
+    pub fn sfx_channel_volume(&self) -> f32 {
+        Self::channel_volume(self.sfx_enabled, self.master_volume * self.sfx_volume)
+    }
+
+    fn channel_volume(enabled: bool, volume: f32) -> f32 {
+        if enabled {
+            volume
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_enabled: true,
+            sfx_enabled: true,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            display_mode: DisplayMode::Windowed,
+            resolution: (crate::WINDOW_WIDTH as u32, crate::WINDOW_HEIGHT as u32),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SaveData {
+    version: u32,
+    scores: Vec<f32>,
+    settings: Settings,
+    bindings: KeyBindings,
+    campaign: CampaignProgress,
+}
+
+impl SaveData {
+    /// Read the save, anything unreadable is logged and replaced with defaults rather than
+    /// stopping the game from starting
+    fn load(backend: &dyn SaveBackend) -> Self {
+        let contents = match backend.read(SAVE_KEY) {
+            Ok(Some(contents)) => contents,
+            Ok(None) => return Self::default(),
+            Err(e) => {
+                error!("Failed to read save, starting fresh: {}", e);
+                return Self::default();
+            }
+        };
+
+        match ron::from_str::<Self>(&contents) {
+            Ok(data) if data.version > SAVE_VERSION => {
+                warn!(
+                    "Save is from a newer version ({} > {}), loading what we can",
+                    data.version, SAVE_VERSION
+                );
+                // Saving writes this version's fields only, so keep the newer file as it was
+                Self::back_up(backend, "newer", &contents);
+                data
+            }
+            Ok(data) => data,
+            Err(e) => {
+                error!("Save is corrupt, starting fresh: {}", e);
+                // Keep the broken file around in case it's worth recovering by hand
+                Self::back_up(backend, "corrupt", &contents);
+                Self::default()
+            }
+        }
+    }
+
+    fn back_up(backend: &dyn SaveBackend, suffix: &str, contents: &str) {
+        let backup_key = format!("{}.{}", SAVE_KEY, suffix);
+        if let Err(e) = backend.write(&backup_key, contents) {
+            error!("Failed to back up {} save: {}", suffix, e);
+        }
+    }
+
+    fn write(&mut self, backend: &dyn SaveBackend) {
+        self.version = SAVE_VERSION;
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|contents| backend.write(SAVE_KEY, &contents));
+        if let Err(e) = result {
+            error!("Failed to save: {}", e);
+        }
+    }
+}
+
+pub struct Save {
+    backend: Box<dyn SaveBackend>,
+}
+
+impl Save {
+    /// Saves to memory only, see [MemoryBackend]
+    pub fn in_memory() -> Self {
+        Self {
+            backend: Box::new(MemoryBackend::default()),
+        }
+    }
+
+    /// Keep something alongside the save that isn't part of it, like a replay
+    pub fn write_extra(&self, key: &str, contents: &str) -> Result<(), String> {
+        self.backend.write(key, contents)
+    }
+
+    pub fn read_extra(&self, key: &str) -> Result<Option<String>, String> {
+        self.backend.read(key)
+    }
+}
+
+/// Writes the save whenever anything in it changes
+fn save_system(
+    save: Res<Save>,
+    total_score: Res<TotalScore>,
+    settings: Res<Settings>,
+    bindings: Res<KeyBindings>,
+    campaign_progress: Res<CampaignProgress>,
+) {
+    let changed = |changed: bool, added: bool| changed && !added;
+    if changed(total_score.is_changed(), total_score.is_added())
+        || changed(settings.is_changed(), settings.is_added())
+        || changed(bindings.is_changed(), bindings.is_added())
+        || changed(campaign_progress.is_changed(), campaign_progress.is_added())
+    {
+        let mut data = SaveData {
+            version: SAVE_VERSION,
+            scores: total_score.scores().to_vec(),
+            settings: settings.clone(),
+            bindings: bindings.clone(),
+            campaign: campaign_progress.clone(),
+        };
+        data.write(save.backend.as_ref());
+    }
+}
+
+/// Loads the save while the app is built, so everything is in place long before the menu
+pub struct SavePlugin;
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let backend = default_backend();
+        let data = SaveData::load(backend.as_ref());
+        app.insert_resource(TotalScore::from_scores(data.scores))
+            .insert_resource(data.settings)
+            .insert_resource(data.bindings)
+            .insert_resource(data.campaign)
+            .insert_resource(Save { backend })
+            .add_system_to_stage(CoreStage::Last, save_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_round_trip_and_corrupt_recovery() {
+        use crate::game::control::Action;
+
+        let backend = MemoryBackend::default();
+        let mut bindings = KeyBindings::default();
+        bindings.set(Action::DoorUp, KeyCode::Up);
+        let mut data = SaveData {
+            scores: vec![12.5, 80.0],
+            settings: Settings {
+                music_enabled: false,
+                sfx_volume: 0.25,
+                ..default()
+            },
+            bindings,
+            ..default()
+        };
+        data.write(&backend);
+
+        let loaded = SaveData::load(&backend);
+        assert_eq!(SAVE_VERSION, loaded.version);
+        assert_eq!(vec![12.5, 80.0], loaded.scores);
+        assert!(!loaded.settings.music_enabled);
+        assert_eq!(0.0, loaded.settings.music_channel_volume());
+        assert_eq!(0.25, loaded.settings.sfx_channel_volume());
+        assert_eq!(&[KeyCode::Up], loaded.bindings.keys(Action::DoorUp));
+
+        backend
+            .write(SAVE_KEY, "(version: 1, scores: [oops")
+            .unwrap();
+        let recovered = SaveData::load(&backend);
+        assert!(recovered.scores.is_empty());
+        assert_eq!(Settings::default(), recovered.settings);
+        assert!(backend.read("save.ron.corrupt").unwrap().is_some());
+
+        let newer = "(version: 99, scores: [40.0], achievements: [\"First mix\"])";
+        backend.write(SAVE_KEY, newer).unwrap();
+        let mut loaded = SaveData::load(&backend);
+        assert_eq!(vec![40.0], loaded.scores);
+        loaded.write(&backend);
+        assert_eq!(
+            Some(newer.to_string()),
+            backend.read("save.ron.newer").unwrap()
+        );
+    }
+}
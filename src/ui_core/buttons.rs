@@ -1,11 +1,13 @@
-use bevy::asset::{AssetServer, Handle};
+use bevy::asset::Handle;
 use bevy::ecs::entity::Entity;
 use bevy::ecs::prelude::{Changed, Query, With};
+use bevy::input::touch::Touches;
 use bevy::math::Vec2;
 use bevy::prelude::*;
 use bevy::render::prelude::Image;
 use bevy::ui::{Node, Size, UiImage, UiRect};
-use std::ops::Not;
+use std::cmp::Ordering;
+use std::ops::{Not, RangeInclusive};
 use std::time::Duration;
 
 pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
@@ -68,25 +70,92 @@ pub fn button_system(
     }
 }
 
-pub fn checkbox_button_system(
-    mut query: Query<(&Interaction, &mut Checkbox, &mut UiImage), Changed<Interaction>>,
+/// The button picked with a gamepad's d-pad, which its south (A) button presses
+#[derive(Default)]
+pub struct GamepadFocus(Option<Entity>);
+
+/// Runs just after bevy's mouse focus, so a press shows up as a click to the same systems
+pub fn gamepad_focus_system(
+    mut focus: ResMut<GamepadFocus>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut buttons: Query<(Entity, &GlobalTransform, &mut Interaction), With<Button>>,
+) {
+    let just_pressed = |types: &[GamepadButtonType]| {
+        gamepad_buttons
+            .get_just_pressed()
+            .any(|button| types.contains(&button.1))
+    };
+    let step: isize = if just_pressed(&[GamepadButtonType::DPadDown, GamepadButtonType::DPadRight])
+    {
+        1
+    } else if just_pressed(&[GamepadButtonType::DPadUp, GamepadButtonType::DPadLeft]) {
+        -1
+    } else {
+        0
+    };
+
+    // Top to bottom then left to right, UI y goes up the screen
+    let mut order: Vec<(Entity, Vec3)> = buttons
+        .iter()
+        .map(|(entity, transform, _)| (entity, transform.translation()))
+        .collect();
+    order.sort_by(|(_, a), (_, b)| {
+        b.y.partial_cmp(&a.y)
+            .unwrap_or(Ordering::Equal)
+            .then(a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal))
+    });
+    // Forgotten once its screen has gone
+    let current = focus
+        .0
+        .and_then(|focused| order.iter().position(|(entity, _)| *entity == focused));
+    if current.is_none() {
+        focus.0 = None;
+    }
+    if step != 0 && !order.is_empty() {
+        let next = match current {
+            Some(index) => (index as isize + step).rem_euclid(order.len() as isize) as usize,
+            None => 0,
+        };
+        focus.0 = Some(order[next].0);
+    }
+
+    let focused = match focus.0 {
+        Some(focused) => focused,
+        None => return,
+    };
+    if let Ok((_, _, mut interaction)) = buttons.get_mut(focused) {
+        if just_pressed(&[GamepadButtonType::South]) {
+            *interaction = Interaction::Clicked;
+        } else if gamepad_buttons
+            .get_just_released()
+            .any(|button| button.1 == GamepadButtonType::South)
+        {
+            *interaction = Interaction::Hovered;
+        } else if *interaction == Interaction::None {
+            *interaction = Interaction::Hovered;
+        }
+    }
+}
+
+pub fn checkbox_button_system<C: Component + Clone>(
+    mut query: Query<(&Interaction, &mut Checkbox, &mut UiImage, &C), Changed<Interaction>>,
     time: Res<Time>,
-    mut event_writer: EventWriter<CheckboxEvent>,
+    mut event_writer: EventWriter<CheckboxEvent<C>>,
 ) {
-    for (interaction, mut checkbox, mut ui_image) in query.iter_mut() {
+    for (interaction, mut checkbox, mut ui_image, widget) in query.iter_mut() {
         checkbox.debounce_timer.tick(time.delta());
         if let Interaction::Clicked = interaction {
             if checkbox.debounce_timer.finished() {
                 checkbox.toggle();
                 *ui_image = UiImage(checkbox.to_current_image());
                 checkbox.debounce_timer.reset();
-                println!("Clicking checkbox");
+                debug!("Clicking checkbox");
                 event_writer.send(CheckboxEvent {
-                    variant: checkbox.variant,
+                    widget: widget.clone(),
                     new_state: checkbox.state,
                 })
             } else {
-                println!("Not toggling checkbox, debounce");
+                debug!("Not toggling checkbox, debounce");
             }
         }
     }
@@ -116,49 +185,65 @@ pub fn make_button_custom_size<C>(
 where
     C: ButtonComponent,
 {
-    let mut text_entity = None;
     let text = button_component.to_text();
+    make_text_button(button_component, text, button_size, parent, font)
+}
+/// Like [make_button_custom_size] but for buttons whose text isn't known up front
+pub fn make_text_button(
+    button_component: impl Component,
+    text: impl Into<String>,
+    button_size: Size<Val>,
+    parent: &mut ChildBuilder,
+    font: Handle<Font>,
+) -> (Entity, Entity) {
+    let mut text_entity = None;
     let button_entity = parent
-        .spawn_bundle(ButtonBundle {
-            style: Style {
-                size: button_size,
-                // center button
-                margin: rect_consts::CENTRED,
-                padding: (UiRect {
-                    left: Val::Percent(0.0),
-                    right: Val::Percent(0.0),
-                    top: Val::Px(100.0),
-                    bottom: Val::Px(100.0),
-                }),
-                // horizontally center child text
-                justify_content: JustifyContent::Center,
-                // vertically center child text
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            color: NORMAL_BUTTON.into(),
-            ..Default::default()
-        })
+        .spawn_bundle(text_button_bundle(button_size))
         .insert(button_component)
         .with_children(|parent| {
-            let text_entity_ = parent
-                .spawn_bundle(TextBundle {
-                    text: Text::from_section(
-                        text,
-                        TextStyle {
-                            font,
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                        },
-                    ),
-                    ..Default::default()
-                })
-                .id();
-            text_entity = Some(text_entity_);
+            text_entity = Some(parent.spawn_bundle(button_text_bundle(text, font)).id());
         })
         .id();
     (button_entity, text_entity.unwrap())
 }
+
+fn text_button_bundle(button_size: Size<Val>) -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            size: button_size,
+            // center button
+            margin: rect_consts::CENTRED,
+            padding: (UiRect {
+                left: Val::Percent(0.0),
+                right: Val::Percent(0.0),
+                top: Val::Px(100.0),
+                bottom: Val::Px(100.0),
+            }),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        color: NORMAL_BUTTON.into(),
+        ..Default::default()
+    }
+}
+
+fn button_text_bundle(text: impl Into<String>, font: Handle<Font>) -> TextBundle {
+    TextBundle {
+        text: Text::from_section(
+            text,
+            TextStyle {
+                font,
+                font_size: 40.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        ),
+        ..Default::default()
+    }
+}
+
 pub fn make_button_custom_image(
     button_component: impl Component,
     button_image: Handle<Image>,
@@ -203,22 +288,40 @@ pub fn make_button_custom_image(
         .id()
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum CheckboxVariant {
-    SFX,
-    Music,
+/// Registers each widget's event and system for widgets tagged with a given component. The
+/// component is handed back in the widget's events, so one enum can tell several widgets apart.
+/// Text inputs also need [text_input_focus_system] added once, labelled [TextInputFocusLabel]
+pub trait WidgetApp {
+    fn add_checkbox<C: Component + Clone>(&mut self) -> &mut Self;
+    fn add_slider<C: Component + Clone>(&mut self) -> &mut Self;
+    fn add_selector<C: Component + Clone>(&mut self) -> &mut Self;
+    fn add_text_input<C: Component + Clone>(&mut self) -> &mut Self;
 }
-impl CheckboxVariant {
-    pub fn to_checked_unchecked_filename(&self) -> (&str, &str) {
-        match self {
-            Self::SFX => ("checkbox/sfx_checked.png", "checkbox/sfx_unchecked.png"),
-            Self::Music => ("checkbox/music_checked.png", "checkbox/music_unchecked.png"),
-        }
+
+impl WidgetApp for App {
+    fn add_checkbox<C: Component + Clone>(&mut self) -> &mut Self {
+        self.add_event::<CheckboxEvent<C>>()
+            .add_system(checkbox_button_system::<C>)
+    }
+
+    fn add_slider<C: Component + Clone>(&mut self) -> &mut Self {
+        self.add_event::<SliderEvent<C>>()
+            .add_system(slider_system::<C>)
+    }
+
+    fn add_selector<C: Component + Clone>(&mut self) -> &mut Self {
+        self.add_event::<SelectorEvent<C>>()
+            .add_system(selector_system::<C>)
+    }
+
+    fn add_text_input<C: Component + Clone>(&mut self) -> &mut Self {
+        self.add_event::<TextInputEvent<C>>()
+            .add_system(text_input_system::<C>.after(TextInputFocusLabel))
     }
 }
 
-pub struct CheckboxEvent {
-    pub variant: CheckboxVariant,
+pub struct CheckboxEvent<C> {
+    pub widget: C,
     pub new_state: CheckboxState,
 }
 
@@ -228,6 +331,16 @@ pub enum CheckboxState {
     Unchecked,
 }
 
+impl From<bool> for CheckboxState {
+    fn from(checked: bool) -> Self {
+        if checked {
+            Self::Checked
+        } else {
+            Self::Unchecked
+        }
+    }
+}
+
 impl Not for CheckboxState {
     type Output = Self;
 
@@ -241,7 +354,6 @@ impl Not for CheckboxState {
 
 #[derive(Debug, Component)]
 pub struct Checkbox {
-    variant: CheckboxVariant,
     checked_image: Handle<Image>,
     unchecked_image: Handle<Image>,
     state: CheckboxState,
@@ -250,15 +362,14 @@ pub struct Checkbox {
 
 impl Checkbox {
     fn new(
-        variant: CheckboxVariant,
+        state: CheckboxState,
         checked_image: Handle<Image>,
         unchecked_image: Handle<Image>,
     ) -> Self {
         Self {
-            variant,
             checked_image,
             unchecked_image,
-            state: CheckboxState::Checked,
+            state,
             debounce_timer: Timer::new(Duration::from_micros(190), false),
         }
     }
@@ -276,15 +387,12 @@ impl Checkbox {
 
 pub fn make_checkbox(
     parent: &mut ChildBuilder,
-    variant: CheckboxVariant,
-    asset_server: &AssetServer,
+    widget: impl Component,
+    state: CheckboxState,
+    checked_image: Handle<Image>,
+    unchecked_image: Handle<Image>,
 ) -> Entity {
-    let (checked, unchecked) = variant.to_checked_unchecked_filename();
-    let checkbox = Checkbox::new(
-        variant,
-        asset_server.load(checked),
-        asset_server.load(unchecked),
-    );
+    let checkbox = Checkbox::new(state, checked_image, unchecked_image);
     parent
         .spawn_bundle(ButtonBundle {
             style: Style {
@@ -302,6 +410,341 @@ pub fn make_checkbox(
         })
         .insert(checkbox)
         .insert(ImageButton)
-        //.insert(button_component)
+        .insert(widget)
         .id()
 }
+
+pub struct SliderEvent<C> {
+    pub widget: C,
+    pub new_value: f32,
+}
+
+const SLIDER_SIZE: Vec2 = Vec2::new(200.0, 16.0);
+const SLIDER_FILL: Color = Color::rgb(0.9, 0.9, 0.9);
+
+/// A value in a range, set by pressing or dragging along its track. Values snap to steps, so a
+/// drag only sends an event when it's moved far enough to matter
+#[derive(Debug, Component)]
+pub struct Slider {
+    value: f32,
+    range: RangeInclusive<f32>,
+    step: f32,
+}
+
+impl Slider {
+    fn snap(&self, value: f32) -> f32 {
+        let (min, max) = (*self.range.start(), *self.range.end());
+        (min + ((value - min) / self.step).round() * self.step).clamp(min, max)
+    }
+
+    /// How far along the track the value is, from 0 to 1
+    fn fraction(&self) -> f32 {
+        let (min, max) = (*self.range.start(), *self.range.end());
+        if max > min {
+            (self.value - min) / (max - min)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The part of a slider's track filled up to its value
+#[derive(Component)]
+struct SliderFill;
+
+pub fn make_slider(
+    parent: &mut ChildBuilder,
+    widget: impl Component,
+    value: f32,
+    range: RangeInclusive<f32>,
+    step: f32,
+) -> Entity {
+    let slider = Slider { value, range, step };
+    let fraction = slider.fraction();
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(SLIDER_SIZE.x), Val::Px(SLIDER_SIZE.y)),
+                margin: rect_consts::CENTRED,
+                ..Default::default()
+            },
+            color: NORMAL_BUTTON.into(),
+            ..Default::default()
+        })
+        .insert(slider)
+        .insert(widget)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(fraction * 100.0), Val::Percent(100.0)),
+                        ..Default::default()
+                    },
+                    color: SLIDER_FILL.into(),
+                    ..Default::default()
+                })
+                .insert(SliderFill);
+        })
+        .id()
+}
+
+/// Where a mouse button or finger is held down, from the bottom left like UI nodes. A gamepad
+/// press doesn't have anywhere along the track so it's left out
+fn held_pointer(windows: &Windows, mouse: &Input<MouseButton>, touches: &Touches) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    if mouse.pressed(MouseButton::Left) {
+        return window.cursor_position();
+    }
+    touches
+        .iter()
+        .next()
+        .map(|touch| Vec2::new(touch.position().x, window.height() - touch.position().y))
+}
+
+pub fn slider_system<C: Component + Clone>(
+    windows: Res<Windows>,
+    mouse: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    mut sliders: Query<(
+        &Interaction,
+        &Node,
+        &GlobalTransform,
+        &mut Slider,
+        &Children,
+        &C,
+    )>,
+    mut fills: Query<&mut Style, With<SliderFill>>,
+    mut event_writer: EventWriter<SliderEvent<C>>,
+) {
+    let pointer = match held_pointer(&windows, &mouse, &touches) {
+        Some(pointer) => pointer,
+        None => return,
+    };
+    for (interaction, node, transform, mut slider, children, widget) in sliders.iter_mut() {
+        // Stays clicked until it's let go, so this follows a drag off the end of the track
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let left = transform.translation().x - node.size.x / 2.0;
+        let (min, max) = (*slider.range.start(), *slider.range.end());
+        let value = slider.snap(min + (pointer.x - left) / node.size.x * (max - min));
+        if value == slider.value {
+            continue;
+        }
+        slider.value = value;
+        for child in children.iter() {
+            if let Ok(mut style) = fills.get_mut(*child) {
+                style.size.width = Val::Percent(slider.fraction() * 100.0);
+            }
+        }
+        event_writer.send(SliderEvent {
+            widget: widget.clone(),
+            new_value: value,
+        });
+    }
+}
+
+pub struct SelectorEvent<C> {
+    pub widget: C,
+    pub index: usize,
+}
+
+/// A button that steps through its options each time it's pressed
+#[derive(Debug, Component)]
+pub struct Selector {
+    options: Vec<String>,
+    index: usize,
+}
+
+pub fn make_selector(
+    parent: &mut ChildBuilder,
+    widget: impl Component,
+    options: Vec<String>,
+    index: usize,
+    button_size: Size<Val>,
+    font: Handle<Font>,
+) -> Entity {
+    let text = options.get(index).cloned().unwrap_or_default();
+    parent
+        .spawn_bundle(text_button_bundle(button_size))
+        .insert(Selector { options, index })
+        .insert(widget)
+        .with_children(|parent| {
+            parent.spawn_bundle(button_text_bundle(text, font));
+        })
+        .id()
+}
+
+pub fn selector_system<C: Component + Clone>(
+    mut selectors: Query<(&Interaction, &mut Selector, &Children, &C), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    mut event_writer: EventWriter<SelectorEvent<C>>,
+) {
+    for (interaction, mut selector, children, widget) in selectors.iter_mut() {
+        if *interaction != Interaction::Clicked || selector.options.is_empty() {
+            continue;
+        }
+        selector.index = (selector.index + 1) % selector.options.len();
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = selector.options[selector.index].clone();
+            }
+        }
+        event_writer.send(SelectorEvent {
+            widget: widget.clone(),
+            index: selector.index,
+        });
+    }
+}
+
+pub struct TextInputEvent<C> {
+    pub widget: C,
+    pub value: String,
+}
+
+/// Text typed in while it has focus, which it gets by being pressed. Enter lets go of it
+#[derive(Debug, Component)]
+pub struct TextInput {
+    value: String,
+    max_len: usize,
+    allowed: fn(char) -> bool,
+    focused: bool,
+}
+
+impl TextInput {
+    pub fn new(value: impl Into<String>, max_len: usize, allowed: fn(char) -> bool) -> Self {
+        Self {
+            value: value.into(),
+            max_len,
+            allowed,
+            focused: false,
+        }
+    }
+
+    /// Takes typing straight away, without being pressed first
+    pub fn focused(mut self) -> Self {
+        self.focused = true;
+        self
+    }
+
+    /// With a caret while it's got focus
+    fn to_text(&self) -> String {
+        if self.focused {
+            format!("{}_", self.value)
+        } else {
+            self.value.clone()
+        }
+    }
+
+    /// Whether it changed
+    fn push(&mut self, c: char) -> bool {
+        if (self.allowed)(c) && self.value.chars().count() < self.max_len {
+            self.value.push(c);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+pub fn make_text_input(
+    parent: &mut ChildBuilder,
+    widget: impl Component,
+    input: TextInput,
+    button_size: Size<Val>,
+    font: Handle<Font>,
+) -> Entity {
+    let text = input.to_text();
+    parent
+        .spawn_bundle(text_button_bundle(button_size))
+        .insert(input)
+        .insert(widget)
+        .with_children(|parent| {
+            parent.spawn_bundle(button_text_bundle(text, font));
+        })
+        .id()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub struct TextInputFocusLabel;
+
+/// Pressing a text input gives it focus and takes it from the others, whatever they're tagged
+pub fn text_input_focus_system(
+    pressed: Query<(Entity, &Interaction), (Changed<Interaction>, With<TextInput>)>,
+    mut inputs: Query<(Entity, &mut TextInput, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let focus = match pressed
+        .iter()
+        .find(|(_, interaction)| **interaction == Interaction::Clicked)
+    {
+        Some((entity, _)) => entity,
+        None => return,
+    };
+    for (entity, mut input, children) in inputs.iter_mut() {
+        input.focused = entity == focus;
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = input.to_text();
+            }
+        }
+    }
+}
+
+pub fn text_input_system<C: Component + Clone>(
+    mut char_events: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut inputs: Query<(&mut TextInput, &Children, &C)>,
+    mut texts: Query<&mut Text>,
+    mut event_writer: EventWriter<TextInputEvent<C>>,
+) {
+    let typed: Vec<char> = char_events.iter().map(|event| event.char).collect();
+    for (mut input, children, widget) in inputs.iter_mut() {
+        if !input.focused {
+            continue;
+        }
+        let mut changed = false;
+        for c in typed.iter() {
+            changed |= input.push(*c);
+        }
+        if keys.just_pressed(KeyCode::Back) {
+            changed |= input.value.pop().is_some();
+        }
+        let unfocused = keys.just_pressed(KeyCode::Return);
+        if unfocused {
+            input.focused = false;
+        }
+        if changed || unfocused {
+            for child in children.iter() {
+                if let Ok(mut text) = texts.get_mut(*child) {
+                    text.sections[0].value = input.to_text();
+                }
+            }
+        }
+        if changed {
+            event_writer.send(TextInputEvent {
+                widget: widget.clone(),
+                value: input.value.clone(),
+            });
+        }
+    }
+}
+
+#[test]
+fn test_slider_and_text_input_values() {
+    let slider = Slider {
+        value: 0.0,
+        range: 0.0..=1.0,
+        step: 0.05,
+    };
+    assert_eq!(0.0, slider.snap(-0.2));
+    assert_eq!(0.5, slider.snap(0.51));
+    assert_eq!(0.55, slider.snap(0.54));
+    assert_eq!(1.0, slider.snap(1.3));
+
+    let mut input = TextInput::new("12", 3, |c| c.is_ascii_digit());
+    assert!(!input.push('a'));
+    assert!(input.push('3'));
+    assert!(!input.push('4'));
+    assert_eq!("123", input.value);
+}
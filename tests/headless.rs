@@ -0,0 +1,101 @@
+use combine_game::game::control::{ControlInput, Tilt};
+use combine_game::game::goals::Countdown;
+use combine_game::game::replay::ReplayLevel;
+use combine_game::headless::{HeadlessGame, HeadlessLevel};
+
+const TICKS_PER_SEC: u32 = 60;
+
+fn tilt_first_cup() -> ControlInput {
+    let mut input = ControlInput::default();
+    input.tilts[0] = Tilt::Clockwise;
+    input
+}
+
+#[test]
+fn tilting_blue_cup_puts_blue_balls_on_the_balance() {
+    let mut game =
+        HeadlessGame::new(HeadlessLevel::File("levels/fifty_fifty.level.ron".into())).unwrap();
+    let blue = game.kind("Blue").unwrap();
+
+    // Let some balls drop into the cups first
+    game.step(6 * TICKS_PER_SEC);
+    game.set_input(tilt_first_cup());
+    game.step(3 * TICKS_PER_SEC);
+    game.set_input(ControlInput::default());
+    // Then give them time to settle on the balance
+    game.step(2 * TICKS_PER_SEC);
+
+    assert!(game.balance().count(&blue) >= 1);
+}
+
+#[test]
+fn same_seed_and_inputs_give_the_same_result() {
+    let run = || {
+        let mut game = HeadlessGame::new(HeadlessLevel::Seed(1234)).unwrap();
+        game.step(6 * TICKS_PER_SEC);
+        game.set_input(tilt_first_cup());
+        game.step(3 * TICKS_PER_SEC);
+        game.set_input(ControlInput::default());
+        game.step(2 * TICKS_PER_SEC);
+        let countdown_started = matches!(game.countdown(), Countdown::Active { .. });
+        (game.balance().total_weight(), countdown_started)
+    };
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn bot_gets_enough_on_the_balance() {
+    let mut game =
+        HeadlessGame::new(HeadlessLevel::File("levels/fifty_fifty.level.ron".into())).unwrap();
+    game.enable_bot();
+    game.step(120 * TICKS_PER_SEC);
+
+    assert!(matches!(game.countdown(), Countdown::Active { .. }));
+}
+
+#[test]
+fn replay_reproduces_the_recorded_score() {
+    let finish = |game: &mut HeadlessGame| {
+        while !game.finished() && game.ticks() < 180 * TICKS_PER_SEC as u64 {
+            game.step(TICKS_PER_SEC);
+        }
+        game.last_replay().expect("The level wasn't recorded")
+    };
+
+    // The goals are left up for a while, like a player reading them, before the bot plays
+    let mut game = HeadlessGame::with_goals_shown(HeadlessLevel::Seed(1234)).unwrap();
+    game.step(2 * TICKS_PER_SEC);
+    game.enable_bot();
+    let recorded = finish(&mut game);
+    assert!(recorded.score.is_some());
+
+    let level = match &recorded.level {
+        ReplayLevel::Seed(seed) => HeadlessLevel::Seed(*seed),
+        ReplayLevel::File(path) => HeadlessLevel::File(path.clone()),
+    };
+    let mut playback = HeadlessGame::with_goals_shown(level).unwrap();
+    for (tick, input) in recorded.inputs() {
+        playback.step((*tick - playback.ticks()) as u32);
+        playback.set_input(*input);
+    }
+    assert_eq!(recorded.score, finish(&mut playback).score);
+}
+
+#[test]
+fn simulate_seeds_prints_only_csv() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_simulate"))
+        .args(["--seeds", "0..3", "--bot", "--max-secs", "30"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // Anything else the game prints would end up in the redirected file
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(4, lines.len(), "{}", stdout);
+    assert!(lines[0].starts_with("seed,"));
+    for (seed, line) in lines[1..].iter().enumerate() {
+        assert!(line.starts_with(&format!("{},", seed)), "{}", line);
+    }
+}